@@ -26,8 +26,7 @@ def serialize_gate(nodes, gate):
     if isinstance(gate, gates.Circuit):
         return {
             'type': 'subcircuit',
-            'inputs': convert_bundle(nodes, gate.inputs),
-            'outputs': convert_bundle(nodes, gate.outputs),
+            **serialize_nodes(nodes, gate),
             'layout': serialize_layout(gate.layout),
             'name': gate.name,
             'gates': [serialize_gate(nodes, gate) for gate in gate.gates],
@@ -35,48 +34,57 @@ def serialize_gate(nodes, gate):
     elif isinstance(gate, gates._NandGate):
         return {
             'type': 'nand',
-            'inputs': convert_bundle(nodes, gate.inputs),
-            'outputs': convert_bundle(nodes, gate.outputs),
+            **serialize_nodes(nodes, gate),
             'layout': serialize_layout(gate.layout),
         }
     elif isinstance(gate, gates._FalseGate):
         return {
             'type': 'false',
-            'inputs': convert_bundle(nodes, gate.inputs),
-            'outputs': convert_bundle(nodes, gate.outputs),
+            **serialize_nodes(nodes, gate),
             'layout': serialize_layout(gate.layout),
         }
     elif isinstance(gate, gates._TrueGate):
         return {
             'type': 'true',
-            'inputs': convert_bundle(nodes, gate.inputs),
-            'outputs': convert_bundle(nodes, gate.outputs),
+            **serialize_nodes(nodes, gate),
             'layout': serialize_layout(gate.layout),
         }
     elif isinstance(gate, gates._UnerrorGate):
         return {
             'type': 'unerror',
-            'inputs': convert_bundle(nodes, gate.inputs),
-            'outputs': convert_bundle(nodes, gate.outputs),
+            **serialize_nodes(nodes, gate),
             'layout': serialize_layout(gate.layout),
         }
     elif isinstance(gate, gates._Button):
         return {
             'type': 'button',
-            'inputs': convert_bundle(nodes, gate.inputs),
-            'outputs': convert_bundle(nodes, gate.outputs),
+            **serialize_nodes(nodes, gate),
             'layout': serialize_layout(gate.layout),
         }
     elif isinstance(gate, gates._TristateBuffer):
         return {
             'type': 'tristate',
-            'inputs': convert_bundle(nodes, gate.inputs),
-            'outputs': convert_bundle(nodes, gate.outputs),
+            **serialize_nodes(nodes, gate),
             'layout': serialize_layout(gate.layout),
         }
     else:
         raise Exception(f'invalid gate {gate}')
 
+def serialize_nodes(nodes, gate):
+    result = {
+        'inputs': convert_bundle(nodes, gate.inputs),
+        'outputs': convert_bundle(nodes, gate.outputs),
+    }
+
+    # names are only written for gates that have any so that gates like nand do not take up more space
+    for (prefix, b) in [('input', gate.inputs), ('output', gate.outputs)]:
+        names = bundle_names(b, None)
+        if any(name is not None for name in names):
+            result[f'{prefix}_names'] = names
+            result[f'{prefix}_bundles'] = bundle_groups(b)
+
+    return result
+
 def serialize_connection(nodes, connection):
     start = connection[0]
     end = connection[1]
@@ -98,6 +106,32 @@ def convert_bundle(nodes, b):
     else:
         raise Exception(f'invalid bundle {b}')
 
+# the name of every bit in a bundle, in the same order as convert_bundle
+# fields of dict products are named by their keys and fields of list products by their indices, like data[2] or in.carry
+def bundle_names(b, name):
+    if isinstance(b, bundle.Bit):
+        return [name]
+    elif isinstance(b, bundle.ListProduct):
+        return sum([bundle_names(subb, f'{name}[{i}]' if name is not None else None) for (i, subb) in enumerate(b.fields)], [])
+    elif isinstance(b, bundle.DictProduct):
+        return sum([bundle_names(subb, f'{name}.{key}' if name is not None else key) for (key, subb) in sorted(b.fields.items())], [])
+    else:
+        raise Exception(f'invalid bundle {b}')
+
+# the fields of a toplevel dict product that have more than one bit in them
+def bundle_groups(b):
+    if not isinstance(b, bundle.DictProduct):
+        return []
+
+    groups = []
+    start = 0
+    for (key, subb) in sorted(b.fields.items()):
+        size = subb.type().size()
+        if size > 1:
+            groups.append({'name': key, 'start': start, 'size': size})
+        start += size
+    return groups
+
 def serialize_layout(layout):
     return {
         'x': layout.position[0],
@@ -0,0 +1,157 @@
+use crate::{
+    layout_overlay::{LayoutOverlay, RemovedLayouts},
+    simulation::{edit, CircuitKey, Gate, GateDirection, Simulation},
+};
+
+// a change to a simulation that can be undone, which is how the editor changes anything (see Project::run)
+//
+// gates and nodes are named by their paths instead of their keys because undoing a removal gives everything that was removed new keys
+// commands are always undone in the opposite order that they were done in, so the simulation is always the same as right after the command was done, and the paths still name the same things
+pub enum Command {
+    // recorded is whether the gate should be in the layout overlay after moving it, which is false when undoing the first time it was moved
+    MoveGate { gate: String, location: (f32, f32), recorded: bool },
+    // recorded works the same as for MoveGate
+    SetDirection { gate: String, direction: GateDirection, recorded: bool },
+    Connect { a: String, b: String },
+    Disconnect { a: String, b: String },
+    // this is only ever made by the editor and not by undoing anything (a gate that was added is removed and then restored), so it can use keys because it runs right away
+    AddGate { parent: Option<CircuitKey>, new_gate: edit::NewGate, location: (f32, f32), direction: GateDirection },
+    // like AddGate, this only runs right away (undoing it removes the gates)
+    Paste { parent: Option<CircuitKey>, copied: edit::CopiedGates, location: (f32, f32) },
+    RemoveGate { gate: String },
+    RestoreGate { removed: edit::RemovedGate, layouts: RemovedLayouts },
+    // the gates all have to be in the same parent
+    ExtractGates { gates: Vec<String> },
+    PutBackExtracted { gate: String, extracted: edit::ExtractedGates },
+    InlineGate { gate: String },
+    // recorded is whether the subcircuit was in the layout overlay before it was inlined
+    RestoreInlined { inlined: edit::InlinedGate, recorded: bool },
+    // done in order and undone together in the opposite order
+    Batch(Vec<Command>),
+}
+
+impl Command {
+    // gives back the command that undoes this one, or None if this one could not be done because what it names does not exist, in which case nothing was changed
+    pub fn apply(self, simulation: &mut Simulation, layout_overlay: &mut LayoutOverlay) -> Option<Command> {
+        match self {
+            Command::MoveGate { gate, location, recorded } => {
+                let gate_key = simulation.find_gate(&gate)?;
+                let gate_location = Gate::location_mut(&mut simulation.circuits, &mut simulation.gates, gate_key);
+                let old_location = (gate_location.x, gate_location.y);
+                *gate_location = location.into();
+                let was_recorded = layout_overlay.contains(&gate);
+                if recorded {
+                    layout_overlay.record(simulation, gate_key);
+                } else {
+                    layout_overlay.forget(&gate);
+                }
+                Some(Command::MoveGate { gate, location: old_location, recorded: was_recorded })
+            }
+            Command::SetDirection { gate, direction, recorded } => {
+                let gate_key = simulation.find_gate(&gate)?;
+                let gate_direction = Gate::direction_mut(&mut simulation.circuits, &mut simulation.gates, gate_key);
+                let old_direction = *gate_direction;
+                *gate_direction = direction;
+                let was_recorded = layout_overlay.contains(&gate);
+                if recorded {
+                    layout_overlay.record(simulation, gate_key);
+                } else {
+                    layout_overlay.forget(&gate);
+                }
+                Some(Command::SetDirection { gate, direction: old_direction, recorded: was_recorded })
+            }
+            Command::Connect { a, b } => {
+                let (a_key, b_key) = (simulation.find_node(&a)?, simulation.find_node(&b)?);
+                if a_key == b_key || simulation.connections.contains(a_key, b_key) {
+                    return None;
+                }
+                simulation.connect(a_key, b_key);
+                Some(Command::Disconnect { a, b })
+            }
+            Command::Disconnect { a, b } => {
+                let (a_key, b_key) = (simulation.find_node(&a)?, simulation.find_node(&b)?);
+                if !simulation.connections.contains(a_key, b_key) {
+                    return None;
+                }
+                simulation.disconnect(a_key, b_key);
+                Some(Command::Connect { a, b })
+            }
+            Command::AddGate { parent, new_gate, location, direction } => {
+                if parent.is_some_and(|ck| !simulation.circuits.contains_key(ck)) {
+                    return None;
+                }
+                if let edit::NewGate::CopyOf(ck) = new_gate {
+                    if !simulation.circuits.contains_key(ck) {
+                        return None;
+                    }
+                }
+                let gate = simulation.add_gate(parent, new_gate, location, direction);
+                Some(Command::RemoveGate { gate: simulation.gate_path(gate)? })
+            }
+            Command::Paste { parent, copied, location } => {
+                if parent.is_some_and(|ck| !simulation.circuits.contains_key(ck)) {
+                    return None;
+                }
+                let gates = simulation.paste_gates(parent, &copied, location);
+                // the pasted gates are at the end of their parent, so removing them starting from the last one keeps the paths of the others the same
+                let undo: Vec<_> = gates.into_iter().rev().filter_map(|gate| Some(Command::RemoveGate { gate: simulation.gate_path(gate)? })).collect();
+                if undo.is_empty() {
+                    return None;
+                }
+                Some(Command::Batch(undo))
+            }
+            Command::RemoveGate { gate } => {
+                let gate_key = simulation.find_gate(&gate)?;
+                let removed = simulation.remove_gate(gate_key)?;
+                let layouts = layout_overlay.gate_removed(&gate);
+                Some(Command::RestoreGate { removed, layouts })
+            }
+            Command::RestoreGate { removed, layouts } => {
+                let gate = simulation.restore_gate(&removed)?;
+                let gate = simulation.gate_path(gate)?;
+                layout_overlay.gate_restored(&gate, layouts);
+                Some(Command::RemoveGate { gate })
+            }
+            Command::ExtractGates { gates } => {
+                let gate_keys = gates.iter().map(|gate| simulation.find_gate(gate)).collect::<Option<Vec<_>>>()?;
+                let layouts = layout_overlay.gate_keys(simulation);
+                let (gate, extracted) = simulation.extract_gates(&gate_keys)?;
+                layout_overlay.gates_moved(simulation, layouts);
+                Some(Command::PutBackExtracted { gate: simulation.gate_path(gate)?, extracted })
+            }
+            Command::PutBackExtracted { gate, extracted } => {
+                let gate_key = simulation.find_gate(&gate)?;
+                let layouts = layout_overlay.gate_keys(simulation);
+                let gates = simulation.put_back_extracted(gate_key, &extracted)?;
+                layout_overlay.gates_moved(simulation, layouts);
+                Some(Command::ExtractGates { gates: gates.into_iter().filter_map(|gate| simulation.gate_path(gate)).collect() })
+            }
+            Command::InlineGate { gate } => {
+                let gate_key = simulation.find_gate(&gate)?;
+                let recorded = layout_overlay.contains(&gate);
+                let layouts = layout_overlay.gate_keys(simulation);
+                let inlined = simulation.inline_gate(gate_key)?;
+                layout_overlay.gates_moved(simulation, layouts);
+                Some(Command::RestoreInlined { inlined, recorded })
+            }
+            Command::RestoreInlined { inlined, recorded } => {
+                let layouts = layout_overlay.gate_keys(simulation);
+                let gate = simulation.restore_inlined(&inlined)?;
+                layout_overlay.gates_moved(simulation, layouts);
+                if recorded {
+                    layout_overlay.record(simulation, gate);
+                }
+                Some(Command::InlineGate { gate: simulation.gate_path(gate)? })
+            }
+            Command::Batch(commands) => {
+                // the commands that could not be done are left out so that undoing this only undoes what happened
+                let mut undo: Vec<_> = commands.into_iter().filter_map(|command| command.apply(simulation, layout_overlay)).collect();
+                if undo.is_empty() {
+                    return None;
+                }
+                undo.reverse();
+                Some(Command::Batch(undo))
+            }
+        }
+    }
+}
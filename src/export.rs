@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use json::JsonValue;
+
+use crate::simulation::{self, hierarchy, location, Gate, GateKey, NodeKey, Simulation};
+
+// writes the simulation in the same format that import reads
+// nodes are numbered again in the order that they appear in the hierarchy, so the numbers will not be the same as in the file that was imported, but importing the result gives the same simulation
+
+pub fn export(simulation: &Simulation, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::write(filename, export_to_json(simulation).dump())?;
+    Ok(())
+}
+
+pub fn export_to_json(simulation: &Simulation) -> JsonValue {
+    let mut node_numbers = HashMap::new();
+
+    let toplevel_gates: Vec<_> = simulation.toplevel_gates.iter().map(|gate| serialize_gate(simulation, &mut node_numbers, *gate)).collect();
+
+    // sorted so that exporting the same simulation twice gives the same file
+    let mut connections: Vec<_> = simulation.connections.iter().map(|(a, b)| (node_numbers[a], node_numbers[b])).map(|(a, b)| if a < b { (a, b) } else { (b, a) }).collect();
+    connections.sort_unstable();
+
+    json::object! {
+        connections: connections.into_iter().map(|(a, b)| json::array![a, b]).collect::<Vec<_>>(),
+        toplevel_gates: toplevel_gates,
+    }
+}
+
+pub(crate) fn serialize_gate(simulation: &Simulation, node_numbers: &mut HashMap<NodeKey, usize>, gate: GateKey) -> JsonValue {
+    let inputs = number_nodes(node_numbers, Gate::inputs(&simulation.circuits, &simulation.gates, gate));
+    let outputs = number_nodes(node_numbers, Gate::outputs(&simulation.circuits, &simulation.gates, gate));
+
+    let mut result = json::object! {
+        inputs: inputs,
+        outputs: outputs,
+        layout: serialize_layout(Gate::location(&simulation.circuits, &simulation.gates, gate), Gate::direction(&simulation.circuits, &simulation.gates, gate)),
+    };
+
+    result["type"] = match &simulation.gates[gate] {
+        Gate::Nand { logic: _, location: _, direction: _ } => "nand".into(),
+        // const gates are named after their value
+        Gate::Const { logic, location: _, direction: _ } => logic.name().into(),
+        Gate::Unerror { logic: _, location: _, direction: _ } => "unerror".into(),
+        Gate::Button { logic: _, location: _, direction: _ } => "button".into(),
+        Gate::TristateBuffer { logic: _, location: _, direction: _ } => "tristate".into(),
+        Gate::Custom(ck) => {
+            let circuit = &simulation.circuits[*ck];
+            result["name"] = circuit.name.as_str().into();
+            result["gates"] = circuit.gates.iter().map(|subgate| serialize_gate(simulation, node_numbers, *subgate)).collect::<Vec<_>>().into();
+            "subcircuit".into()
+        }
+    };
+
+    serialize_port_names(&mut result, "input", Gate::input_names(&simulation.circuits, &simulation.gates, gate), Gate::num_inputs(&simulation.circuits, &simulation.gates, gate));
+    serialize_port_names(&mut result, "output", Gate::output_names(&simulation.circuits, &simulation.gates, gate), Gate::num_outputs(&simulation.circuits, &simulation.gates, gate));
+
+    result
+}
+
+pub(crate) fn serialize_layout(location: &location::GateLocation, direction: simulation::GateDirection) -> JsonValue {
+    let direction = match direction {
+        simulation::GateDirection::LTR => "ltr",
+        simulation::GateDirection::RTL => "rtl",
+        simulation::GateDirection::TTB => "ttb",
+        simulation::GateDirection::BTT => "btt",
+    };
+    json::object! { x: location.x, y: location.y, direction: direction }
+}
+
+fn number_nodes(node_numbers: &mut HashMap<NodeKey, usize>, nodes: &[NodeKey]) -> Vec<usize> {
+    nodes
+        .iter()
+        .map(|node| {
+            let number = node_numbers.len();
+            node_numbers.insert(*node, number);
+            number
+        })
+        .collect()
+}
+
+// like the python generator, gates without any names do not get the fields at all
+fn serialize_port_names(result: &mut JsonValue, prefix: &str, names: &hierarchy::PortNames, num_nodes: usize) {
+    if names.is_empty() {
+        return;
+    }
+
+    result[format!("{prefix}_names")] = (0..num_nodes).map(|i| names.name(i).map_or(JsonValue::Null, JsonValue::from)).collect::<Vec<_>>().into();
+    result[format!("{prefix}_bundles")] = names.bundles().iter().map(|bundle| json::object! { name: bundle.name.as_str(), start: bundle.start, size: bundle.size }).collect::<Vec<_>>().into();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        import,
+        simulation::{tests::PROJECT, Simulation},
+    };
+
+    // connections by the paths of their ends, which stay the same when the nodes are numbered again
+    fn connection_paths(simulation: &Simulation) -> BTreeSet<(String, String)> {
+        simulation.connections().iter().map(|(a, b)| (simulation.node_path(*a).unwrap(), simulation.node_path(*b).unwrap())).map(|(a, b)| if a < b { (a, b) } else { (b, a) }).collect()
+    }
+
+    #[test]
+    fn export_then_import_gives_the_same_simulation() {
+        let original = import::import(PROJECT).unwrap();
+        let exported = std::env::temp_dir().join(format!("logicgates_export_test_{}.json", std::process::id()));
+        super::export(&original, exported.to_str().unwrap()).unwrap();
+        let reimported = import::import(exported.to_str().unwrap());
+        std::fs::remove_file(&exported).unwrap();
+        let reimported = reimported.unwrap();
+
+        assert_eq!(original.circuits().len(), reimported.circuits().len());
+        assert_eq!(original.gates().len(), reimported.gates().len());
+        assert_eq!(original.nodes().len(), reimported.nodes().len());
+        assert_eq!(connection_paths(&original), connection_paths(&reimported));
+        // the gates, their names, bundles and layouts, and the connections between them all end up the same
+        assert_eq!(super::export_to_json(&original), super::export_to_json(&reimported));
+    }
+}
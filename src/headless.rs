@@ -0,0 +1,164 @@
+// running a project without opening a window, for scripts and ci
+// every option takes one node or gate because paths can have commas and spaces in them, and text output is tab separated for the same reason
+
+use std::collections::HashMap;
+
+use logicgates::{
+    import,
+    simulation::{hierarchy, logic, Gate, GateKey, NodeKey, Simulation},
+    vcd,
+};
+
+const USAGE: &str = "\
+logicgates run <project.json> [options]
+    --ticks <n>             number of ticks to run (default 1)
+    --watch <node>          node to print, can be given multiple times
+    --toggle <tick>:<node>  toggle the button that drives the given node right before the given tick, can be given multiple times
+                            (a button can also be given by the path of the button gate itself)
+                            the tick ends at the first ':', so everything after it is the node even if it has ':' in it
+    --every-tick            print the watched nodes after every tick instead of only at the end
+    --json                  print json instead of text
+    --vcd <file>            also record the watched nodes every tick and write them to a value change dump
+    --vcd-circuit <gate>    also record every node inside the gate with this path (main for everything) in the value change dump, can be given multiple times
+    --help                  print this
+
+nodes can be given either by their number in the project file or by their path, like main/button[3]/out[0]
+";
+
+struct Options {
+    project: String,
+    ticks: usize,
+    watches: Vec<String>,
+    toggles: Vec<(usize, String)>,
+    every_tick: bool,
+    json: bool,
+    vcd: Option<String>,
+    vcd_circuits: Vec<String>,
+}
+
+pub(crate) fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if args.iter().any(|arg| arg == "--help") {
+        print!("{USAGE}");
+        return Ok(());
+    }
+    let options = parse_args(args)?;
+    let (mut simulation, node_mapping) = import::import_with_node_mapping(&options.project)?;
+
+    let watches = options.watches.iter().map(|spec| Ok((spec, resolve_node(&simulation, &node_mapping, spec)?))).collect::<Result<Vec<_>, String>>()?;
+    let mut toggles: HashMap<usize, Vec<GateKey>> = HashMap::new();
+    for (tick, spec) in &options.toggles {
+        let button = match simulation.find_gate(spec) {
+            Some(gate) if is_button(&simulation, gate) => gate,
+            Some(_) => Err(format!("gate '{spec}' is not a button"))?,
+            None => button_driving(&simulation, resolve_node(&simulation, &node_mapping, spec)?).ok_or(format!("node '{spec}' is not the output of a button"))?,
+        };
+        toggles.entry(*tick).or_default().push(button);
+    }
+
+    let mut vcd = match &options.vcd {
+        Some(_) => {
+            let mut vcd_nodes: Vec<_> = watches.iter().map(|(_, node)| *node).collect();
+            for gate_path in &options.vcd_circuits {
+                vcd_nodes.extend(vcd::nodes_in(&simulation, gate_path).ok_or(format!("no gate at path '{gate_path}'"))?);
+            }
+            Some(vcd::VcdRecorder::new(&simulation, &vcd_nodes))
+        }
+        None => None,
+    };
+
+    let mut rows = Vec::new();
+    for tick in 0..options.ticks {
+        for button in toggles.get(&tick).into_iter().flatten() {
+            simulation.toggle_button(*button);
+        }
+        simulation.update();
+        if let Some(vcd) = &mut vcd {
+            vcd.record(&simulation, tick);
+        }
+
+        if options.every_tick || tick + 1 == options.ticks {
+            rows.push((tick, watches.iter().map(|(_, node)| simulation.node_value(*node)).collect::<Vec<_>>()));
+        }
+    }
+
+    if let (Some(vcd), Some(vcd_file)) = (&vcd, &options.vcd) {
+        vcd.save(vcd_file)?;
+    }
+
+    if options.json {
+        let row_to_json = |(tick, values): &(usize, Vec<logic::Value>)| {
+            let mut values_json = json::JsonValue::new_object();
+            for ((spec, _), value) in watches.iter().zip(values) {
+                values_json[spec.as_str()] = value_str(*value).into();
+            }
+            json::object! { tick: *tick, values: values_json }
+        };
+        let output = if options.every_tick { json::JsonValue::Array(rows.iter().map(row_to_json).collect()) } else { rows.last().map(row_to_json).unwrap_or(json::JsonValue::Null) };
+        println!("{}", output.pretty(4));
+    } else {
+        println!("tick\t{}", watches.iter().map(|(spec, _)| spec.as_str()).collect::<Vec<_>>().join("\t"));
+        for (tick, values) in &rows {
+            println!("{tick}\t{}", values.iter().map(|value| value_str(*value)).collect::<Vec<_>>().join("\t"));
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let mut options = Options { project: String::new(), ticks: 1, watches: Vec::new(), toggles: Vec::new(), every_tick: false, json: false, vcd: None, vcd_circuits: Vec::new() };
+    let mut project = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("expected value after {name}"));
+        match arg.as_str() {
+            "--ticks" => options.ticks = value("--ticks")?.parse().map_err(|_| "--ticks must be a number")?,
+            "--watch" => options.watches.push(value("--watch")?.clone()),
+            "--toggle" => {
+                let toggle = value("--toggle")?;
+                let (tick, node) = toggle.split_once(':').ok_or(format!("--toggle '{toggle}' has no ':', expected <tick>:<node>"))?;
+                let tick = tick.parse().map_err(|_| format!("--toggle '{toggle}' starts with '{tick}', which is not a tick number, expected <tick>:<node>"))?;
+                options.toggles.push((tick, node.to_string()));
+            }
+            "--every-tick" => options.every_tick = true,
+            "--json" => options.json = true,
+            "--vcd" => options.vcd = Some(value("--vcd")?.clone()),
+            "--vcd-circuit" => options.vcd_circuits.push(value("--vcd-circuit")?.clone()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{arg}' (see --help)")),
+            _ if project.is_none() => project = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument '{arg}'")),
+        }
+    }
+
+    options.project = project.ok_or("expected project file (see --help)")?;
+    Ok(options)
+}
+
+// a node is either a number from the project file or a path
+fn resolve_node(simulation: &Simulation, node_mapping: &HashMap<usize, NodeKey>, spec: &str) -> Result<NodeKey, String> {
+    match spec.parse::<usize>() {
+        Ok(number) => node_mapping.get(&number).copied().ok_or(format!("no node {number} in project")),
+        Err(_) => simulation.find_node(spec).ok_or(format!("no node at path '{spec}'")),
+    }
+}
+
+fn is_button(simulation: &Simulation, gate: GateKey) -> bool {
+    matches!(simulation.gates()[gate], Gate::Button { logic: _, location: _, direction: _ })
+}
+
+fn button_driving(simulation: &Simulation, node: NodeKey) -> Option<GateKey> {
+    match simulation.nodes()[node].parent.kind() {
+        hierarchy::NodeParentKind::GateOut(gate, _) if is_button(simulation, gate) => Some(gate),
+        _ => None,
+    }
+}
+
+fn value_str(value: logic::Value) -> &'static str {
+    match value {
+        logic::Value::H => "1",
+        logic::Value::L => "0",
+        logic::Value::Z => "z",
+        logic::Value::X => "x",
+    }
+}
@@ -6,7 +6,12 @@ use crate::simulation;
 
 // TODO: clean this all up, esp repetitive code to get a field from an object
 
-pub(crate) fn import(filename: &str) -> Result<simulation::Simulation, Box<dyn std::error::Error>> {
+pub fn import(filename: &str) -> Result<simulation::Simulation, Box<dyn std::error::Error>> {
+    Ok(import_with_node_mapping(filename)?.0)
+}
+
+// also returns which node each node number in the file became
+pub fn import_with_node_mapping(filename: &str) -> Result<(simulation::Simulation, HashMap<usize, simulation::NodeKey>), Box<dyn std::error::Error>> {
     let mut simulation = simulation::Simulation::new();
     let mut node_mapping = HashMap::new();
 
@@ -37,10 +42,10 @@ pub(crate) fn import(filename: &str) -> Result<simulation::Simulation, Box<dyn s
         simulation::connections::connect(&mut simulation.connections, &mut simulation.nodes, node_a, node_b);
     }
 
-    Ok(simulation)
+    Ok((simulation, node_mapping))
 }
 
-fn parse_gate(
+pub(crate) fn parse_gate(
     circuit_map: &mut simulation::CircuitMap,
     gate_map: &mut simulation::GateMap,
     node_map: &mut simulation::NodeMap,
@@ -53,45 +58,53 @@ fn parse_gate(
     let JsonValue::Array(gate_outputs) = gate.remove("outputs").ok_or("gate must have field 'outputs'")? else { return Err("gate outputs must be array".to_string()); };
     let JsonValue::Object(gate_layout) = gate.remove("layout").ok_or("gate must have field 'layout'")? else { return Err("gate layout must be object".to_string()); };
     let (gate_direction, gate_pos) = parse_layout(gate_layout)?;
+    let input_names = parse_port_names(gate.remove("input_names"), gate.remove("input_bundles"), gate_inputs.len())?;
+    let output_names = parse_port_names(gate.remove("output_names"), gate.remove("output_bundles"), gate_outputs.len())?;
 
     match &*gate_type {
         "nand" => Ok(gate_map.insert_with_key(|gk| {
-            let logic = simulation::logic::NandLogic::new(node_map, gk);
+            let mut logic = simulation::logic::NandLogic::new(node_map, gk);
+            logic.nodes.set_names(input_names, output_names);
             assign_node_mapping(node_mapping, logic.nodes.inputs(), &gate_inputs);
             assign_node_mapping(node_mapping, logic.nodes.outputs(), &gate_outputs);
             simulation::Gate::Nand { logic, location: gate_pos.into(), direction: gate_direction }
         })),
 
         "true" => Ok(gate_map.insert_with_key(|gk| {
-            let logic = simulation::logic::ConstLogic::new(node_map, gk, true);
+            let mut logic = simulation::logic::ConstLogic::new(node_map, gk, true);
+            logic.nodes.set_names(input_names, output_names);
             assign_node_mapping(node_mapping, logic.nodes.inputs(), &gate_inputs);
             assign_node_mapping(node_mapping, logic.nodes.outputs(), &gate_outputs);
             simulation::Gate::Const { logic, location: gate_pos.into(), direction: gate_direction }
         })),
 
         "false" => Ok(gate_map.insert_with_key(|gk| {
-            let logic = simulation::logic::ConstLogic::new(node_map, gk, false);
+            let mut logic = simulation::logic::ConstLogic::new(node_map, gk, false);
+            logic.nodes.set_names(input_names, output_names);
             assign_node_mapping(node_mapping, logic.nodes.inputs(), &gate_inputs);
             assign_node_mapping(node_mapping, logic.nodes.outputs(), &gate_outputs);
             simulation::Gate::Const { logic, location: gate_pos.into(), direction: gate_direction }
         })),
 
         "unerror" => Ok(gate_map.insert_with_key(|gk| {
-            let logic = simulation::logic::UnerrorLogic::new(node_map, gk);
+            let mut logic = simulation::logic::UnerrorLogic::new(node_map, gk);
+            logic.nodes.set_names(input_names, output_names);
             assign_node_mapping(node_mapping, logic.nodes.inputs(), &gate_inputs);
             assign_node_mapping(node_mapping, logic.nodes.outputs(), &gate_outputs);
             simulation::Gate::Unerror { logic, location: gate_pos.into(), direction: gate_direction }
         })),
 
         "button" => Ok(gate_map.insert_with_key(|gk| {
-            let logic = simulation::logic::ButtonLogic::new(node_map, gk);
+            let mut logic = simulation::logic::ButtonLogic::new(node_map, gk);
+            logic.nodes.set_names(input_names, output_names);
             assign_node_mapping(node_mapping, logic.nodes.inputs(), &gate_inputs);
             assign_node_mapping(node_mapping, logic.nodes.outputs(), &gate_outputs);
             simulation::Gate::Button { logic, location: gate_pos.into(), direction: gate_direction }
         })),
 
         "tristate" => Ok(gate_map.insert_with_key(|gk| {
-            let logic = simulation::logic::TristateBufferLogic::new(node_map, gk);
+            let mut logic = simulation::logic::TristateBufferLogic::new(node_map, gk);
+            logic.nodes.set_names(input_names, output_names);
             assign_node_mapping(node_mapping, logic.nodes.inputs(), &gate_inputs);
             assign_node_mapping(node_mapping, logic.nodes.outputs(), &gate_outputs);
             simulation::Gate::TristateBuffer { logic, location: gate_pos.into(), direction: gate_direction }
@@ -101,7 +114,8 @@ fn parse_gate(
             let JsonValue::Array(subgates) = gate.remove("gates").ok_or("subcircuit gate must have field 'gates'")? else { return Err("gate subgates must be array".to_string()); };
             let name = gate.remove("name").ok_or("subcircuit gate must have field 'name'")?.take_string().ok_or("subcircuit name must be string")?;
 
-            let ck = circuit_map.insert_with_key(|ck| simulation::Circuit::new(ck, node_map, name, gate_pos.into(), gate_direction, gate_inputs.len(), gate_outputs.len())); // TODO: names
+            let ck = circuit_map.insert_with_key(|ck| simulation::Circuit::new(ck, node_map, name, gate_pos.into(), gate_direction, gate_inputs.len(), gate_outputs.len()));
+            circuit_map[ck].nodes.set_names(input_names, output_names);
 
             assign_node_mapping(node_mapping, circuit_map[ck].nodes.inputs(), &gate_inputs);
             assign_node_mapping(node_mapping, circuit_map[ck].nodes.outputs(), &gate_outputs);
@@ -118,7 +132,7 @@ fn parse_gate(
     }
 }
 
-fn parse_layout(mut layout: json::object::Object) -> Result<(simulation::GateDirection, (f32, f32)), String> {
+pub(crate) fn parse_layout(mut layout: json::object::Object) -> Result<(simulation::GateDirection, (f32, f32)), String> {
     let x = layout.remove("x").ok_or("gate layout must have field 'x'")?.as_f32().ok_or("gate layout x must be number")?;
     let y = layout.remove("y").ok_or("gate layout must have field 'y'")?.as_f32().ok_or("gate layout y must be number")?;
     let direction = layout.remove("direction").ok_or("gate layout must have field 'direction'")?.take_string().ok_or("gate layout direection must be string")?;
@@ -135,6 +149,47 @@ fn parse_layout(mut layout: json::object::Object) -> Result<(simulation::GateDir
     Ok((direction, (x, y)))
 }
 
+// names and bundles are optional so that projects generated before they existed still load
+fn parse_port_names(names: Option<JsonValue>, bundles: Option<JsonValue>, num_nodes: usize) -> Result<simulation::hierarchy::PortNames, String> {
+    let names = match names {
+        Some(JsonValue::Array(names)) => names
+            .into_iter()
+            .map(|name| match name {
+                JsonValue::Null => Ok(None),
+                mut name => Ok(Some(name.take_string().ok_or("port name must be string or null")?)),
+            })
+            .collect::<Result<Vec<_>, String>>()?,
+        Some(_) => return Err("port names must be array".to_string()),
+        None => Vec::new(),
+    };
+    if !names.is_empty() && names.len() != num_nodes {
+        return Err(format!("expected {} port names but got {}", num_nodes, names.len()));
+    }
+
+    let bundles = match bundles {
+        Some(JsonValue::Array(bundles)) => bundles
+            .into_iter()
+            .map(|bundle| {
+                let JsonValue::Object(mut bundle) = bundle else { return Err("bundle must be object".to_string()) };
+                let name = bundle.remove("name").ok_or("bundle must have field 'name'")?.take_string().ok_or("bundle name must be string")?;
+                let start = bundle.remove("start").ok_or("bundle must have field 'start'")?.as_usize().ok_or("bundle start must be number")?;
+                let size = bundle.remove("size").ok_or("bundle must have field 'size'")?.as_usize().ok_or("bundle size must be number")?;
+                if size == 0 {
+                    return Err(format!("bundle '{}' has no nodes", name));
+                }
+                if start + size > num_nodes {
+                    return Err(format!("bundle '{}' goes past the end of the nodes", name));
+                }
+                Ok(simulation::hierarchy::Bundle { name, start, size })
+            })
+            .collect::<Result<Vec<_>, String>>()?,
+        Some(_) => return Err("port bundles must be array".to_string()),
+        None => Vec::new(),
+    };
+
+    Ok(simulation::hierarchy::PortNames::new(names, bundles))
+}
+
 // TODO: figure out a better way than to panic
 fn assign_node_mapping(node_mapping: &mut HashMap<usize, simulation::NodeKey>, nodes: &[simulation::NodeKey], numbers: &[JsonValue]) {
     assert_eq!(nodes.len(), numbers.len());
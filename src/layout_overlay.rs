@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use json::JsonValue;
+
+use crate::{
+    export, import,
+    simulation::{location, path, Gate, GateDirection, GateKey, Simulation},
+};
+
+// positions and directions of gates that were changed by hand, kept in a separate file next to the project
+// the project file is regenerated by the python helper library and would lose any changes made in the ui, so they are stored here instead, keyed by the path of the gate (see simulation::path)
+//
+// the file looks like
+//     { "main/adder4[8]": { "x": 10, "y": 20, "direction": "ltr" }, ... }
+
+pub struct LayoutOverlay {
+    // btree map so that the file is written in the same order every time
+    gates: BTreeMap<String, (location::GateLocation, GateDirection)>,
+    changed: bool,
+}
+
+// the layouts that LayoutOverlay::gate_removed forgot, which LayoutOverlay::gate_restored puts back
+pub struct RemovedLayouts {
+    gates: BTreeMap<String, (location::GateLocation, GateDirection)>,
+}
+
+impl LayoutOverlay {
+    pub fn new() -> LayoutOverlay {
+        LayoutOverlay { gates: BTreeMap::new(), changed: false }
+    }
+
+    // a project that has never had its layout changed does not have an overlay file, so that gives an empty overlay
+    pub fn load(filename: &str) -> Result<LayoutOverlay, Box<dyn std::error::Error>> {
+        let contents = match std::fs::read_to_string(filename) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(LayoutOverlay::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let JsonValue::Object(overlay) = json::parse(&contents)? else { return Err("layout overlay must be object".into()) };
+        let mut gates = BTreeMap::new();
+        for (path, layout) in overlay.iter() {
+            let JsonValue::Object(layout) = layout.clone() else { return Err(format!("layout of '{path}' must be object").into()) };
+            let (direction, pos) = import::parse_layout(layout)?;
+            gates.insert(path.to_string(), (pos.into(), direction));
+        }
+
+        Ok(LayoutOverlay { gates, changed: false })
+    }
+
+    pub fn save(&mut self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut overlay = JsonValue::new_object();
+        for (path, (location, direction)) in &self.gates {
+            overlay[path.as_str()] = export::serialize_layout(location, *direction);
+        }
+        std::fs::write(filename, overlay.pretty(4))?;
+        self.changed = false;
+        Ok(())
+    }
+
+    // the overlay file for a project, which is the project file with .layout added before the extension
+    pub fn path_for_project(project_path: &str) -> String {
+        match project_path.strip_suffix(".json") {
+            Some(stem) => format!("{stem}.layout.json"),
+            None => format!("{project_path}.layout.json"),
+        }
+    }
+
+    // moves every gate in the overlay to where the overlay says it should be
+    // returns the paths that no longer name a gate (the structure of the circuit changed since they were recorded), which are left in the overlay in case they come back
+    pub fn apply(&self, simulation: &mut Simulation) -> Vec<&str> {
+        let mut missing = Vec::new();
+        for (path, (location, direction)) in &self.gates {
+            match simulation.find_gate(path) {
+                Some(gate) => {
+                    *Gate::location_mut(&mut simulation.circuits, &mut simulation.gates, gate) = (location.x, location.y).into();
+                    *Gate::direction_mut(&mut simulation.circuits, &mut simulation.gates, gate) = *direction;
+                }
+                None => missing.push(path.as_str()),
+            }
+        }
+        missing
+    }
+
+    pub fn set(&mut self, path: String, location: (f32, f32), direction: GateDirection) {
+        self.gates.insert(path, (location.into(), direction));
+        self.changed = true;
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.gates.contains_key(path)
+    }
+
+    pub fn forget(&mut self, path: &str) {
+        if self.gates.remove(path).is_some() {
+            self.changed = true;
+        }
+    }
+
+    // stores the current position and direction of a gate
+    pub fn record(&mut self, simulation: &Simulation, gate: GateKey) {
+        let Some(path) = simulation.gate_path(gate) else { return };
+        let location = Gate::location(&simulation.circuits, &simulation.gates, gate);
+        self.set(path, (location.x, location.y), Gate::direction(&simulation.circuits, &simulation.gates, gate));
+    }
+
+    // forgets a gate that was removed (with Simulation::remove_gate) and everything inside of it
+    // the gates after it in the same parent moved down by one, so their paths (and the paths of everything inside of them) are renamed to match
+    pub fn gate_removed(&mut self, removed_path: &str) -> RemovedLayouts {
+        let (removed, kept) = std::mem::take(&mut self.gates).into_iter().partition(|(gate_path, _)| gate_path == removed_path || gate_path.starts_with(&format!("{removed_path}/")));
+        self.gates = kept;
+        let removed = RemovedLayouts { gates: removed };
+        if !removed.gates.is_empty() {
+            self.changed = true;
+        }
+
+        if let Some((parent, index)) = split_path(removed_path) {
+            self.shift_siblings(parent, index + 1, -1);
+        }
+        removed
+    }
+
+    // undoes gate_removed after the gate was put back with Simulation::restore_gate
+    pub fn gate_restored(&mut self, restored_path: &str, removed: RemovedLayouts) {
+        if let Some((parent, index)) = split_path(restored_path) {
+            self.shift_siblings(parent, index, 1);
+        }
+        for (gate_path, layout) in removed.gates {
+            self.gates.insert(gate_path, layout);
+            self.changed = true;
+        }
+    }
+
+    // the gate that every path in the overlay names, for gates_moved
+    pub fn gate_keys(&self, simulation: &Simulation) -> Vec<(String, Option<GateKey>)> {
+        self.gates.keys().map(|gate_path| (gate_path.clone(), simulation.find_gate(gate_path))).collect()
+    }
+
+    // renames the layouts of gates that were moved to somewhere else in the simulation without changing their keys (like with Simulation::extract_gates) and records where they are now
+    // gate_keys comes from before they were moved, and the layouts of gates that are not in the simulation anymore are forgotten
+    pub fn gates_moved(&mut self, simulation: &Simulation, gate_keys: Vec<(String, Option<GateKey>)>) {
+        let mut moved = Vec::new();
+        for (old_path, gate) in gate_keys {
+            let Some(gate) = gate else { continue };
+            let new_path = simulation.gate_path(gate);
+            if new_path.as_ref() == Some(&old_path) || self.gates.remove(&old_path).is_none() {
+                continue;
+            }
+            self.changed = true;
+            if let Some(new_path) = new_path {
+                let location = Gate::location(&simulation.circuits, &simulation.gates, gate);
+                moved.push((new_path, ((location.x, location.y).into(), Gate::direction(&simulation.circuits, &simulation.gates, gate))));
+            }
+        }
+        // these are put back after everything was taken out so that a gate moving to where another one was does not overwrite it
+        self.gates.extend(moved);
+    }
+
+    // changes the index of every gate in parent with an index of at least first_index by offset, along with the paths of everything inside of them
+    fn shift_siblings(&mut self, parent: &str, first_index: usize, offset: isize) {
+        let old_gates = std::mem::take(&mut self.gates);
+        for (gate_path, layout) in old_gates {
+            let renamed = gate_path.strip_prefix(&format!("{parent}/")).and_then(|rest| {
+                let (segment, inside) = match rest.split_once('/') {
+                    Some((segment, inside)) => (segment, Some(inside)),
+                    None => (rest, None),
+                };
+                let (name, index) = path::parse_segment(segment)?;
+                if index < first_index {
+                    return None;
+                }
+                let index = index.checked_add_signed(offset)?;
+                Some(match inside {
+                    Some(inside) => format!("{parent}/{}/{inside}", path::format_segment(&name, index)),
+                    None => format!("{parent}/{}", path::format_segment(&name, index)),
+                })
+            });
+            match renamed {
+                Some(renamed) => {
+                    self.gates.insert(renamed, layout);
+                    self.changed = true;
+                }
+                None => {
+                    self.gates.insert(gate_path, layout);
+                }
+            }
+        }
+    }
+
+    // if anything was recorded since the last save
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+// the parent of the gate at path and the index of the gate in it
+fn split_path(gate_path: &str) -> Option<(&str, usize)> {
+    let (parent, segment) = gate_path.rsplit_once('/')?;
+    Some((parent, path::parse_segment(segment)?.1))
+}
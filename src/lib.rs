@@ -0,0 +1,15 @@
+#![allow(clippy::upper_case_acronyms)]
+#![allow(clippy::new_without_default)]
+#![allow(clippy::manual_non_exhaustive)]
+#![warn(clippy::semicolon_if_nothing_returned)]
+
+// the simulation itself, without any of the ui, so that it can be used from other programs
+// the sfml front end in main.rs is just one user of this
+
+pub mod command;
+pub mod export;
+pub mod import;
+pub mod layout_overlay;
+pub mod project;
+pub mod vcd;
+pub mod simulation;
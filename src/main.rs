@@ -1,25 +1,42 @@
 #![allow(clippy::upper_case_acronyms)]
 #![allow(clippy::type_complexity)]
+#![allow(clippy::too_many_arguments)] // widgets take a lens for every piece of state they use
 #![warn(clippy::semicolon_if_nothing_returned)]
 
+#[cfg(feature = "gui")]
 use std::rc::Rc;
 
-pub(crate) mod import;
-pub(crate) mod simulation;
+#[cfg(feature = "gui")]
+use logicgates::{export, import, layout_overlay::LayoutOverlay, project::Project};
+
+pub(crate) mod headless;
+#[cfg(feature = "gui")]
 pub(crate) mod theme;
+#[cfg(feature = "gui")]
 #[macro_use]
 pub(crate) mod ui;
+#[cfg(feature = "gui")]
 pub(crate) mod graphics;
+#[cfg(feature = "gui")]
 pub(crate) mod view;
 
+#[cfg(feature = "gui")]
 struct App {
     start_time: std::time::Instant,
     last_update: std::time::Instant,
+    // the view that keyboard events go to, which views take by calling take_focus when they are clicked on
+    // views are made again for every event, so this has to be an id that stays the same when they are (see ViewId::keyed)
+    focus: std::cell::Cell<Option<view::id::ViewId>>,
 }
 
+#[cfg(feature = "gui")]
 impl App {
     fn new() -> Self {
-        Self { start_time: std::time::Instant::now(), last_update: std::time::Instant::now() }
+        Self { start_time: std::time::Instant::now(), last_update: std::time::Instant::now(), focus: std::cell::Cell::new(None) }
+    }
+
+    fn take_focus(&self, id: view::id::ViewId) {
+        self.focus.set(Some(id));
     }
 
     fn time_since_start(&self) -> std::time::Duration {
@@ -32,13 +49,16 @@ impl App {
 }
 
 // TODO: find a better place to put this and reorganize everything
+#[cfg(feature = "gui")]
 struct LogicGates {
-    simulation: simulation::Simulation,
+    project: Project,
+    project_path: String,
     ticks_per_second: isize,
     ui: ui::UI,
     font: Rc<sfml::SfBox<graphics::Font>>, // not ideal but
 }
 
+#[cfg(feature = "gui")]
 impl LogicGates {
     fn new() -> LogicGates {
         // TODO: convert panics to Result?
@@ -49,11 +69,51 @@ impl LogicGates {
             font_kit::handle::Handle::Path { path, font_index: _ } => graphics::Font::from_file(&path.to_string_lossy()).expect("could not load font"), // TODO: figure out how to handle font_index
             font_kit::handle::Handle::Memory { bytes: _, font_index: _ } => unimplemented!("loading font from memory"),
         };
-        LogicGates { simulation: import::import(&std::env::args().nth(1).expect("expected input file")).unwrap(), ticks_per_second: 20, ui: ui::UI::new(), font: Rc::new(font) }
+        let project_path = std::env::args().nth(1).expect("expected input file");
+        let mut simulation = import::import(&project_path).unwrap();
+        let layout_overlay = LayoutOverlay::load(&LayoutOverlay::path_for_project(&project_path)).unwrap();
+        for path in layout_overlay.apply(&mut simulation) {
+            eprintln!("warning: layout overlay has gate '{path}' which is not in the project");
+        }
+        LogicGates { project: Project::new(simulation, layout_overlay), project_path, ticks_per_second: 20, ui: ui::UI::new(), font: Rc::new(font) }
+    }
+
+    // writes the simulation back to the file that it was loaded from
+    fn save(&self) {
+        if let Err(e) = export::export(&self.project.simulation, &self.project_path) {
+            eprintln!("error: could not save to '{}': {e}", self.project_path);
+        }
+    }
+
+    fn save_layout_overlay(&mut self) {
+        let path = LayoutOverlay::path_for_project(&self.project_path);
+        if let Err(e) = self.project.layout_overlay.save(&path) {
+            eprintln!("error: could not save layout overlay to '{path}': {e}");
+        }
     }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("run") {
+        if let Err(e) = headless::run(&args[2..]) {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    run_gui();
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_gui() {
+    eprintln!("error: logicgates was built without the gui, only 'logicgates run' is available");
+    std::process::exit(1);
+}
+
+#[cfg(feature = "gui")]
+fn run_gui() {
     use sfml::{
         graphics::{RenderTarget, RenderWindow},
         window::{Event, Style},
@@ -78,12 +138,16 @@ fn main() {
                 _ => view::event(&app, &window, &mut logic_gates, event),
             }
         }
+        if logic_gates.project.layout_overlay.changed() {
+            logic_gates.save_layout_overlay();
+        }
 
         // update
         let mut time_since_last_update = std::time::Instant::now() - app.last_update;
         let time_between_updates = std::time::Duration::from_secs(1) / logic_gates.ticks_per_second as u32;
         while time_since_last_update > time_between_updates {
-            simulation::logic::update(&mut logic_gates.simulation.gates, &mut logic_gates.simulation.nodes);
+            logic_gates.project.simulation.update();
+            logic_gates.ui.waveform.record(&logic_gates.project.simulation);
             time_since_last_update -= time_between_updates;
             app.last_update = std::time::Instant::now();
         }
@@ -95,7 +159,10 @@ fn main() {
     }
 }
 
+#[cfg(feature = "gui")]
 fn view(app: &App, logic_gates: &LogicGates) -> impl view::ViewWithoutLayout<LogicGates> {
+    use view::lens::Lens;
+
     let mut id_maker = view::id::ViewIdMaker::new();
 
     let btree_view = ui::widgets::btree::btree(
@@ -103,14 +170,36 @@ fn view(app: &App, logic_gates: &LogicGates) -> impl view::ViewWithoutLayout<Log
         &mut id_maker,
         logic_gates,
         view::lens::Closures::new(|logic_gates: &LogicGates| &logic_gates.ui.btree, |logic_gates| &mut logic_gates.ui.btree),
-        |id_maker, simulation_lens, logic_gates| {
-            ui::widgets::simulation::simulation(
-                id_maker,
-                simulation_lens,
-                view::lens::Closures::new(|logic_gates: &LogicGates| &logic_gates.simulation, |logic_gates| &mut logic_gates.simulation),
-                &logic_gates.font,
-                logic_gates,
-            )
+        |id_maker, pane_lens, logic_gates| {
+            let waveform_lens = view::lens::Closures::new(|logic_gates: &LogicGates| &logic_gates.ui.waveform, |logic_gates| &mut logic_gates.ui.waveform);
+            let palette_lens = view::lens::Closures::new(|logic_gates: &LogicGates| &logic_gates.ui.palette, |logic_gates| &mut logic_gates.ui.palette);
+            if pane_lens.with(logic_gates, |pane| matches!(pane, ui::Pane::Simulation(_))) {
+                ui::widgets::either::Either::Left(ui::widgets::simulation::simulation(
+                    id_maker,
+                    pane_lens.single_id(),
+                    view::lens::Compose::new(
+                        pane_lens,
+                        view::lens::Closures::new(
+                            |pane: &ui::Pane| match pane {
+                                ui::Pane::Simulation(state) => state,
+                                ui::Pane::Waveform => unreachable!("pane changed kind while its view existed"),
+                            },
+                            |pane| match pane {
+                                ui::Pane::Simulation(state) => state,
+                                ui::Pane::Waveform => unreachable!("pane changed kind while its view existed"),
+                            },
+                        ),
+                    ),
+                    view::lens::Closures::new(|logic_gates: &LogicGates| &logic_gates.project.simulation, |logic_gates| &mut logic_gates.project.simulation),
+                    view::lens::Closures::new(|logic_gates: &LogicGates| &logic_gates.project, |logic_gates| &mut logic_gates.project),
+                    waveform_lens,
+                    palette_lens,
+                    &logic_gates.font,
+                    logic_gates,
+                ))
+            } else {
+                ui::widgets::either::Either::Right(ui::widgets::waveform::waveform(id_maker, waveform_lens, &logic_gates.font, logic_gates))
+            }
         },
     );
 
@@ -136,9 +225,27 @@ fn view(app: &App, logic_gates: &LogicGates) -> impl view::ViewWithoutLayout<Log
         logic_gates,
     );
 
+    let palette = ui::widgets::palette::palette(
+        &mut id_maker,
+        view::lens::Closures::new(|logic_gates: &LogicGates| &logic_gates.ui.palette, |logic_gates| &mut logic_gates.ui.palette),
+        view::lens::Closures::new(|logic_gates: &LogicGates| &logic_gates.project.simulation, |logic_gates| &mut logic_gates.project.simulation),
+        &logic_gates.font,
+        logic_gates,
+    );
+
+    let save_button = ui::widgets::button::labeled_button(
+        &mut id_maker,
+        logic_gates,
+        view::lens::Closures::new(|logic_gates: &LogicGates| &logic_gates.ui.save_button_state, |logic_gates| &mut logic_gates.ui.save_button_state),
+        "save",
+        &logic_gates.font,
+        |_, logic_gates| logic_gates.save(),
+    );
+
     let flow_view = flow! {
         vertical
 
+        palette: palette,
         rect0: rects[0].take().unwrap(),
         rect1: rects[1].take().unwrap(),
         rect2: rects[2].take().unwrap(),
@@ -160,6 +267,7 @@ fn view(app: &App, logic_gates: &LogicGates) -> impl view::ViewWithoutLayout<Log
         rect18: rects[18].take().unwrap(),
         rect19: rects[19].take().unwrap(),
         slider: subticks_slider,
+        save: save_button,
     };
 
     ui::widgets::slide_over::slide_over(
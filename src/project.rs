@@ -0,0 +1,65 @@
+use crate::{
+    command::Command,
+    layout_overlay::LayoutOverlay,
+    simulation::{edit::CopiedGates, Simulation},
+};
+
+// a simulation that is being edited, together with its layout overlay and the history of changes that can be undone
+// the simulation can still be changed directly for things that are not edits, like running it or pressing buttons
+
+pub struct Project {
+    pub simulation: Simulation,
+    pub layout_overlay: LayoutOverlay,
+    // the last gates that were copied, which are kept here so that they can be pasted into any view of the simulation
+    pub clipboard: Option<CopiedGates>,
+    // the commands that undo the last changes, with the most recent at the end, and the same for redoing changes that were undone
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+}
+
+impl Project {
+    pub fn new(simulation: Simulation, layout_overlay: LayoutOverlay) -> Project {
+        Project { simulation, layout_overlay, clipboard: None, undo_stack: Vec::new(), redo_stack: Vec::new() }
+    }
+
+    // returns whether the command did anything
+    pub fn run(&mut self, command: Command) -> bool {
+        match command.apply(&mut self.simulation, &mut self.layout_overlay) {
+            Some(undo) => {
+                self.push_undo(undo);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // for changes that were already made without going through run, like dragging a gate, which moves it every time the mouse moves
+    pub fn push_undo(&mut self, undo: Command) {
+        self.undo_stack.push(undo);
+        self.redo_stack.clear();
+    }
+
+    pub fn undo(&mut self) -> bool {
+        Self::move_between(&mut self.simulation, &mut self.layout_overlay, &mut self.undo_stack, &mut self.redo_stack)
+    }
+    pub fn redo(&mut self) -> bool {
+        Self::move_between(&mut self.simulation, &mut self.layout_overlay, &mut self.redo_stack, &mut self.undo_stack)
+    }
+
+    // applies the last command in from and puts the command that reverses it in to
+    fn move_between(simulation: &mut Simulation, layout_overlay: &mut LayoutOverlay, from: &mut Vec<Command>, to: &mut Vec<Command>) -> bool {
+        let Some(command) = from.pop() else { return false };
+        match command.apply(simulation, layout_overlay) {
+            Some(reverse) => {
+                to.push(reverse);
+                true
+            }
+            // this should not happen because everything that changes the structure of the simulation goes through here, but if it does, the history is no use anymore
+            None => {
+                from.clear();
+                to.clear();
+                false
+            }
+        }
+    }
+}
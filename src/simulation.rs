@@ -1,48 +1,53 @@
-pub(crate) mod connections;
-pub(crate) mod hierarchy;
-pub(crate) mod location;
-pub(crate) mod logic;
+pub mod connections;
+pub mod edit;
+pub mod hierarchy;
+pub mod location;
+pub mod logic;
+pub mod path;
 
 slotmap::new_key_type! {
-    pub(crate) struct CircuitKey;
-    pub(crate) struct GateKey;
-    pub(crate) struct NodeKey;
+    pub struct CircuitKey;
+    pub struct GateKey;
+    pub struct NodeKey;
 }
-pub(crate) type CircuitMap = slotmap::SlotMap<CircuitKey, Circuit>;
-pub(crate) type GateMap = slotmap::SlotMap<GateKey, Gate>;
-pub(crate) type NodeMap = slotmap::SlotMap<NodeKey, Node>;
+pub type CircuitMap = slotmap::SlotMap<CircuitKey, Circuit>;
+pub type GateMap = slotmap::SlotMap<GateKey, Gate>;
+pub type NodeMap = slotmap::SlotMap<NodeKey, Node>;
 
-pub(crate) struct Simulation {
+// everything that changes the simulation goes through the methods on Simulation (or the functions they call) so that the scheduler always knows what it has to look at again
+// the maps themselves can only be read from outside of this crate
+pub struct Simulation {
     pub(crate) circuits: CircuitMap,
     pub(crate) gates: GateMap,
     pub(crate) nodes: NodeMap,
     pub(crate) connections: connections::Connections,
+    pub(crate) scheduler: logic::Scheduler,
 
     pub(crate) toplevel_gates: hierarchy::GateChildren,
 }
 
-pub(crate) struct Circuit {
-    pub(crate) name: String,
-    pub(crate) gates: hierarchy::GateChildren,
-    pub(crate) nodes: hierarchy::NodeChildren<Vec<NodeKey>, Vec<NodeKey>>,
-    pub(crate) location: location::GateLocation,
-    pub(crate) direction: GateDirection,
+pub struct Circuit {
+    pub name: String,
+    pub gates: hierarchy::GateChildren,
+    pub nodes: hierarchy::NodeChildren<Vec<NodeKey>, Vec<NodeKey>>,
+    pub location: location::GateLocation,
+    pub direction: GateDirection,
 }
 
-pub(crate) struct Node {
-    pub(crate) logic: logic::NodeLogic,
-    pub(crate) parent: hierarchy::NodeParent,
-    pub(crate) connections: connections::NodeConnections,
+pub struct Node {
+    pub logic: logic::NodeLogic,
+    pub parent: hierarchy::NodeParent,
+    pub connections: connections::NodeConnections,
 }
 
 #[derive(Copy, Clone)]
-pub(crate) enum GateDirection {
+pub enum GateDirection {
     LTR,
     RTL,
     TTB,
     BTT,
 }
-pub(crate) enum Gate {
+pub enum Gate {
     Nand { logic: logic::NandLogic, location: location::GateLocation, direction: GateDirection },
     Const { logic: logic::ConstLogic, location: location::GateLocation, direction: GateDirection },
     Unerror { logic: logic::UnerrorLogic, location: location::GateLocation, direction: GateDirection },
@@ -52,19 +57,144 @@ pub(crate) enum Gate {
 }
 
 impl Simulation {
-    pub(crate) fn new() -> Simulation {
+    pub fn new() -> Simulation {
         Simulation {
             circuits: CircuitMap::with_key(),
             gates: GateMap::with_key(),
             nodes: NodeMap::with_key(),
             connections: connections::Connections::new(),
+            scheduler: logic::Scheduler::new(),
             toplevel_gates: hierarchy::GateChildren::new(),
         }
     }
+
+    pub fn update(&mut self) {
+        logic::update(&mut self.gates, &mut self.nodes, &mut self.scheduler);
+    }
+
+    pub fn circuits(&self) -> &CircuitMap {
+        &self.circuits
+    }
+    pub fn gates(&self) -> &GateMap {
+        &self.gates
+    }
+    pub fn nodes(&self) -> &NodeMap {
+        &self.nodes
+    }
+    pub fn connections(&self) -> &connections::Connections {
+        &self.connections
+    }
+    pub fn toplevel_gates(&self) -> &hierarchy::GateChildren {
+        &self.toplevel_gates
+    }
+
+    // where a gate is drawn does not change what it does, so this does not need to schedule anything
+    pub fn set_gate_location(&mut self, gate: GateKey, location: (f32, f32)) {
+        *Gate::location_mut(&mut self.circuits, &mut self.gates, gate) = location.into();
+    }
+
+    pub fn node_value(&self, node: NodeKey) -> logic::Value {
+        logic::get_node_value(&self.nodes, node)
+    }
+
+    // the name that the project file gave this node, if any
+    pub fn node_name(&self, node: NodeKey) -> Option<&str> {
+        match self.nodes[node].parent.kind() {
+            hierarchy::NodeParentKind::GateIn(gk, i) => Gate::input_names(&self.circuits, &self.gates, gk).name(i),
+            hierarchy::NodeParentKind::GateOut(gk, i) => Gate::output_names(&self.circuits, &self.gates, gk).name(i),
+            hierarchy::NodeParentKind::CircuitIn(ck, i) => self.circuits[ck].nodes.input_names().name(i),
+            hierarchy::NodeParentKind::CircuitOut(ck, i) => self.circuits[ck].nodes.output_names().name(i),
+        }
+    }
+
+    pub fn gate_path(&self, gate: GateKey) -> Option<String> {
+        path::gate_path(self, gate)
+    }
+    pub fn node_path(&self, node: NodeKey) -> Option<String> {
+        path::node_path(self, node)
+    }
+    pub fn find_gate(&self, path: &str) -> Option<GateKey> {
+        path::find_gate(self, path)
+    }
+    pub fn find_node(&self, path: &str) -> Option<NodeKey> {
+        path::find_node(self, path)
+    }
+
+    pub fn buttons(&self) -> impl Iterator<Item = GateKey> + '_ {
+        self.gates.iter().filter_map(|(gk, gate)| if let Gate::Button { logic: _, location: _, direction: _ } = gate { Some(gk) } else { None })
+    }
+    pub fn button_pressed(&self, button: GateKey) -> bool {
+        let Gate::Button { logic, location: _, direction: _ } = &self.gates[button] else { panic!("button_pressed called on gate that is not a button") };
+        matches!(logic::get_node_production(&self.nodes, logic.nodes.outputs()[0]), Some(logic::Value::H))
+    }
+    pub fn set_button(&mut self, button: GateKey, pressed: bool) {
+        let Gate::Button { logic, location: _, direction: _ } = &self.gates[button] else { panic!("set_button called on gate that is not a button") };
+        logic::set_node_production(&mut self.nodes, &mut self.scheduler, logic.nodes.outputs()[0], if pressed { logic::Value::H } else { logic::Value::L });
+    }
+    pub fn toggle_button(&mut self, button: GateKey) {
+        self.set_button(button, !self.button_pressed(button));
+    }
+
+    // unlike connections::connect and connections::disconnect, these also schedule the nets that changed so that the change shows up while the simulation is running
+    pub fn connect(&mut self, a: NodeKey, b: NodeKey) {
+        connections::connect(&mut self.connections, &mut self.nodes, a, b);
+        self.scheduler.schedule_net(a);
+    }
+    pub fn disconnect(&mut self, a: NodeKey, b: NodeKey) {
+        connections::disconnect(&mut self.connections, &mut self.nodes, a, b);
+        self.scheduler.schedule_net(a);
+        self.scheduler.schedule_net(b);
+    }
+
+    pub fn add_gate(&mut self, parent: Option<CircuitKey>, new_gate: edit::NewGate, location: (f32, f32), direction: GateDirection) -> GateKey {
+        edit::add_gate(self, parent, new_gate, location, direction)
+    }
+    pub fn remove_gate(&mut self, gate: GateKey) -> Option<edit::RemovedGate> {
+        edit::remove_gate(self, gate)
+    }
+    pub fn restore_gate(&mut self, removed: &edit::RemovedGate) -> Option<GateKey> {
+        edit::restore_gate(self, removed)
+    }
+    pub fn copy_gates(&self, gates: &[GateKey]) -> edit::CopiedGates {
+        edit::copy_gates(self, gates)
+    }
+    pub fn paste_gates(&mut self, parent: Option<CircuitKey>, copied: &edit::CopiedGates, location: (f32, f32)) -> Vec<GateKey> {
+        edit::paste_gates(self, parent, copied, location)
+    }
+    pub fn extract_gates(&mut self, gates: &[GateKey]) -> Option<(GateKey, edit::ExtractedGates)> {
+        edit::extract_gates(self, gates)
+    }
+    pub fn put_back_extracted(&mut self, gate: GateKey, extracted: &edit::ExtractedGates) -> Option<Vec<GateKey>> {
+        edit::put_back_extracted(self, gate, extracted)
+    }
+    pub fn inline_gate(&mut self, gate: GateKey) -> Option<edit::InlinedGate> {
+        edit::inline_gate(self, gate)
+    }
+    pub fn restore_inlined(&mut self, inlined: &edit::InlinedGate) -> Option<GateKey> {
+        edit::restore_inlined(self, inlined)
+    }
+    pub fn circuit_definitions(&self) -> Vec<CircuitKey> {
+        edit::circuit_definitions(self)
+    }
+}
+
+impl GateDirection {
+    // the direction that signals go through the gate after turning it a quarter turn clockwise
+    pub fn rotated_clockwise(self) -> GateDirection {
+        match self {
+            GateDirection::LTR => GateDirection::TTB,
+            GateDirection::TTB => GateDirection::RTL,
+            GateDirection::RTL => GateDirection::BTT,
+            GateDirection::BTT => GateDirection::LTR,
+        }
+    }
+    pub fn rotated_counterclockwise(self) -> GateDirection {
+        self.rotated_clockwise().rotated_clockwise().rotated_clockwise()
+    }
 }
 
 impl Circuit {
-    pub(crate) fn new(circuit_key: CircuitKey, nodes: &mut NodeMap, name: String, location: location::GateLocation, direction: GateDirection, num_inputs: usize, num_outputs: usize) -> Circuit {
+    pub fn new(circuit_key: CircuitKey, nodes: &mut NodeMap, name: String, location: location::GateLocation, direction: GateDirection, num_inputs: usize, num_outputs: usize) -> Circuit {
         Circuit {
             name,
             gates: hierarchy::GateChildren::new(),
@@ -75,7 +205,7 @@ impl Circuit {
     }
 }
 impl Gate {
-    pub(crate) fn name<'s: 'r, 'c: 'r, 'r>(&'s self, circuits: &'c CircuitMap) -> &'r str {
+    pub fn name<'s: 'r, 'c: 'r, 'r>(&'s self, circuits: &'c CircuitMap) -> &'r str {
         match self {
             Gate::Nand { logic, location: _, direction: _ } => logic.name(),
             Gate::Const { logic, location: _, direction: _ } => logic.name(),
@@ -86,7 +216,7 @@ impl Gate {
         }
     }
 
-    pub(crate) fn inputs<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> &'r [NodeKey] {
+    pub fn inputs<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> &'r [NodeKey] {
         match &gates[gate] {
             Gate::Nand { logic, location: _, direction: _ } => logic.nodes.inputs(),
             Gate::Const { logic, location: _, direction: _ } => logic.nodes.inputs(),
@@ -96,7 +226,7 @@ impl Gate {
             Gate::Custom(circuit_idx) => circuits[*circuit_idx].nodes.inputs(),
         }
     }
-    pub(crate) fn outputs<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> &'r [NodeKey] {
+    pub fn outputs<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> &'r [NodeKey] {
         match &gates[gate] {
             Gate::Nand { logic, location: _, direction: _ } => logic.nodes.outputs(),
             Gate::Const { logic, location: _, direction: _ } => logic.nodes.outputs(),
@@ -107,14 +237,35 @@ impl Gate {
         }
     }
 
-    pub(crate) fn num_inputs(circuits: &CircuitMap, gates: &GateMap, gate: GateKey) -> usize {
+    pub fn input_names<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> &'r hierarchy::PortNames {
+        match &gates[gate] {
+            Gate::Nand { logic, location: _, direction: _ } => logic.nodes.input_names(),
+            Gate::Const { logic, location: _, direction: _ } => logic.nodes.input_names(),
+            Gate::Unerror { logic, location: _, direction: _ } => logic.nodes.input_names(),
+            Gate::Button { logic, location: _, direction: _ } => logic.nodes.input_names(),
+            Gate::TristateBuffer { logic, location: _, direction: _ } => logic.nodes.input_names(),
+            Gate::Custom(circuit_idx) => circuits[*circuit_idx].nodes.input_names(),
+        }
+    }
+    pub fn output_names<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> &'r hierarchy::PortNames {
+        match &gates[gate] {
+            Gate::Nand { logic, location: _, direction: _ } => logic.nodes.output_names(),
+            Gate::Const { logic, location: _, direction: _ } => logic.nodes.output_names(),
+            Gate::Unerror { logic, location: _, direction: _ } => logic.nodes.output_names(),
+            Gate::Button { logic, location: _, direction: _ } => logic.nodes.output_names(),
+            Gate::TristateBuffer { logic, location: _, direction: _ } => logic.nodes.output_names(),
+            Gate::Custom(circuit_idx) => circuits[*circuit_idx].nodes.output_names(),
+        }
+    }
+
+    pub fn num_inputs(circuits: &CircuitMap, gates: &GateMap, gate: GateKey) -> usize {
         Gate::inputs(circuits, gates, gate).len()
     }
-    pub(crate) fn num_outputs(circuits: &CircuitMap, gates: &GateMap, gate: GateKey) -> usize {
+    pub fn num_outputs(circuits: &CircuitMap, gates: &GateMap, gate: GateKey) -> usize {
         Gate::outputs(circuits, gates, gate).len()
     }
 
-    pub(crate) fn location<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> &'r location::GateLocation {
+    pub fn location<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> &'r location::GateLocation {
         match &gates[gate] {
             Gate::Nand { logic: _, location, direction: _ }
             | Gate::Const { logic: _, location, direction: _ }
@@ -135,7 +286,7 @@ impl Gate {
         }
     }
 
-    pub(crate) fn direction<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> GateDirection {
+    pub fn direction<'c: 'r, 'g: 'r, 'r>(circuits: &'c CircuitMap, gates: &'g GateMap, gate: GateKey) -> GateDirection {
         match &gates[gate] {
             Gate::Nand { logic: _, location: _, direction }
             | Gate::Const { logic: _, location: _, direction }
@@ -145,4 +296,46 @@ impl Gate {
             Gate::Custom(sck) => circuits[*sck].direction,
         }
     }
+    pub(crate) fn direction_mut<'c: 'r, 'g: 'r, 'r>(circuits: &'c mut CircuitMap, gates: &'g mut GateMap, gate: GateKey) -> &'r mut GateDirection {
+        match &mut gates[gate] {
+            Gate::Nand { logic: _, location: _, direction }
+            | Gate::Const { logic: _, location: _, direction }
+            | Gate::Unerror { logic: _, location: _, direction }
+            | Gate::Button { logic: _, location: _, direction }
+            | Gate::TristateBuffer { logic: _, location: _, direction } => direction,
+            Gate::Custom(sck) => &mut circuits[*sck].direction,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::Simulation;
+
+    // the project that the tests run on, which is main_adder from project/project.py
+    pub(crate) const PROJECT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/project/adder.json");
+
+    // updates a and b side by side and checks that every node of a that is also in b has the same value after every update
+    // with a seed, the same random buttons of a are pressed in both (a few at a time so that changes are still going through when the next ones come)
+    // before_update gets the tick and both simulations right before they are updated, for anything else that the test does to them
+    pub(crate) fn assert_same_behaviour(a: &mut Simulation, b: &mut Simulation, ticks: usize, mut seed: Option<u32>, mut before_update: impl FnMut(usize, &mut Simulation, &mut Simulation)) {
+        let buttons: Vec<_> = a.buttons().collect();
+        for tick in 0..ticks {
+            if let Some(seed) = &mut seed {
+                *seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                if *seed >> 28 < 4 && !buttons.is_empty() {
+                    let button = buttons[(*seed >> 8) as usize % buttons.len()];
+                    a.toggle_button(button);
+                    b.toggle_button(button);
+                }
+            }
+            before_update(tick, a, b);
+            a.update();
+            b.update();
+
+            for (node, _) in a.nodes.iter().filter(|(node, _)| b.nodes.contains_key(*node)) {
+                assert!(a.node_value(node) == b.node_value(node), "{:?} differs on tick {tick}", a.node_path(node));
+            }
+        }
+    }
 }
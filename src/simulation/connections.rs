@@ -2,27 +2,38 @@ use std::collections::{hash_set, HashSet};
 
 use crate::simulation::{NodeKey, NodeMap};
 
-pub(crate) struct Connections {
+pub struct Connections {
     connections: HashSet<(NodeKey, NodeKey)>,
 }
 
-pub(crate) struct NodeConnections {
+pub struct NodeConnections {
     adjacent: HashSet<NodeKey>,
+
+    // disjoint set forest of nets (groups of nodes that are electrically connected), kept up to date by connect and disconnect
+    net_parent: Option<NodeKey>,
+    net_rank: u32,
+    // every net is also a circular linked list so that all of the nodes in it can be found (None means this node links back to itself)
+    net_next: Option<NodeKey>,
 }
 
 impl Connections {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self { connections: HashSet::new() }
     }
 
-    pub(crate) fn iter(&self) -> hash_set::Iter<(NodeKey, NodeKey)> {
+    pub fn iter(&self) -> hash_set::Iter<'_, (NodeKey, NodeKey)> {
         self.connections.iter()
     }
+
+    pub fn contains(&self, a: NodeKey, b: NodeKey) -> bool {
+        let (lower, higher) = if a < b { (a, b) } else { (b, a) };
+        self.connections.contains(&(lower, higher))
+    }
 }
 
 impl NodeConnections {
     pub(super) fn new() -> Self {
-        Self { adjacent: HashSet::new() }
+        Self { adjacent: HashSet::new(), net_parent: None, net_rank: 0, net_next: None }
     }
 
     pub(super) fn adjacent(&self) -> &HashSet<NodeKey> {
@@ -35,10 +46,93 @@ pub(crate) fn connect(connections: &mut Connections, nodes: &mut NodeMap, a: Nod
     connections.connections.insert((lower, higher));
     nodes[lower].connections.adjacent.insert(higher);
     nodes[higher].connections.adjacent.insert(lower);
+
+    union_nets(nodes, a, b);
 }
 pub(crate) fn disconnect(connections: &mut Connections, nodes: &mut NodeMap, a: NodeKey, b: NodeKey) {
     let (lower, higher) = if a < b { (a, b) } else { (b, a) };
     connections.connections.remove(&(lower, higher));
     nodes[lower].connections.adjacent.remove(&higher);
     nodes[higher].connections.adjacent.remove(&lower);
+
+    // disjoint sets cannot be split, so the net that a and b were in gets rebuilt from scratch
+    // this only touches the nodes that were in that net, so it is proportional to the size of the net and not the whole simulation
+    let a_net = rebuild_net(nodes, a);
+    if !a_net.contains(&b) {
+        rebuild_net(nodes, b);
+    }
+}
+
+// nets {{{1
+pub(crate) fn find_net(nodes: &mut NodeMap, node: NodeKey) -> NodeKey {
+    let mut root = node;
+    while let Some(parent) = nodes[root].connections.net_parent {
+        root = parent;
+    }
+
+    // path compression
+    let mut cur = node;
+    while let Some(parent) = nodes[cur].connections.net_parent {
+        if parent != root {
+            nodes[cur].connections.net_parent = Some(root);
+        }
+        cur = parent;
+    }
+
+    root
+}
+
+pub fn net_members(nodes: &NodeMap, node: NodeKey) -> Vec<NodeKey> {
+    let mut members = vec![node];
+    let mut cur = nodes[node].connections.net_next.unwrap_or(node);
+    while cur != node {
+        members.push(cur);
+        cur = nodes[cur].connections.net_next.unwrap_or(cur);
+    }
+    members
+}
+
+fn union_nets(nodes: &mut NodeMap, a: NodeKey, b: NodeKey) {
+    let a_root = find_net(nodes, a);
+    let b_root = find_net(nodes, b);
+    if a_root == b_root {
+        return;
+    }
+
+    // union by rank
+    let a_rank = nodes[a_root].connections.net_rank;
+    let b_rank = nodes[b_root].connections.net_rank;
+    let (child, parent) = if a_rank < b_rank { (a_root, b_root) } else { (b_root, a_root) };
+    nodes[child].connections.net_parent = Some(parent);
+    if a_rank == b_rank {
+        nodes[parent].connections.net_rank += 1;
+    }
+
+    // splice the two member lists together
+    let a_next = nodes[a_root].connections.net_next.unwrap_or(a_root);
+    let b_next = nodes[b_root].connections.net_next.unwrap_or(b_root);
+    nodes[a_root].connections.net_next = Some(b_next);
+    nodes[b_root].connections.net_next = Some(a_next);
+}
+
+// makes all of the nodes reachable from start into one flat net rooted at start, returning the nodes in the net
+fn rebuild_net(nodes: &mut NodeMap, start: NodeKey) -> HashSet<NodeKey> {
+    let mut net = HashSet::new();
+    let mut order = Vec::new();
+    let mut queue = vec![start];
+    while let Some(cur) = queue.pop() {
+        if net.insert(cur) {
+            order.push(cur);
+            queue.extend(nodes[cur].connections.adjacent().iter().copied());
+        }
+    }
+
+    for (i, &node) in order.iter().enumerate() {
+        nodes[node].connections.net_parent = if node == start { None } else { Some(start) };
+        nodes[node].connections.net_rank = 0;
+        nodes[node].connections.net_next = Some(order[(i + 1) % order.len()]);
+    }
+    nodes[start].connections.net_rank = if net.len() > 1 { 1 } else { 0 };
+
+    net
 }
@@ -0,0 +1,688 @@
+// changes to the structure of a simulation while it is running, which is what the editor in the app is made of
+// everything here keeps the scheduler up to date so that the changes show up in the next update without restarting the simulation
+
+use std::collections::{HashMap, HashSet};
+
+use json::JsonValue;
+
+use crate::{
+    export, import,
+    simulation::{connections, hierarchy, logic, path, Circuit, CircuitKey, Gate, GateDirection, GateKey, NodeKey, Simulation},
+};
+
+// what a new gate should be
+#[derive(Copy, Clone)]
+pub enum NewGate {
+    Nand,
+    Const(bool),
+    Unerror,
+    Button,
+    TristateBuffer,
+    // another instance of the same subcircuit as this circuit, with everything inside of it copied
+    CopyOf(CircuitKey),
+}
+
+// everything needed to put a removed gate back where it was with restore_gate
+pub struct RemovedGate {
+    path: String,
+    // the gate in the same format as in the project file, which numbers its nodes
+    gate: JsonValue,
+    inner_connections: Vec<(usize, usize)>,
+    // connections to nodes that were not removed, by the paths that those nodes have after the removal
+    outer_connections: Vec<(usize, String)>,
+}
+
+// where the gates moved by extract_gates were, for put_back_extracted
+pub struct ExtractedGates {
+    indices: Vec<usize>,
+    locations: Vec<(f32, f32)>,
+}
+
+// everything needed to put back a subcircuit that was inlined by inline_gate with restore_inlined
+pub struct InlinedGate {
+    path: String,
+    name: String,
+    input_names: hierarchy::PortNames,
+    output_names: hierarchy::PortNames,
+    num_inputs: usize,
+    num_outputs: usize,
+    location: (f32, f32),
+    direction: GateDirection,
+    // the locations that the gates inside had, which were relative to the subcircuit
+    locations: Vec<(f32, f32)>,
+    // every connection to an input or output of the subcircuit, by the paths that the nodes have while it is there
+    boundary_connections: Vec<(String, String)>,
+    // the connections that replaced them, by the paths that the nodes have after inlining
+    spliced_connections: Vec<(String, String)>,
+}
+
+// gates copied out of a simulation by copy_gates, which can be pasted any number of times by paste_gates
+#[derive(Clone)]
+pub struct CopiedGates {
+    // in the same format as in the project file, with the nodes numbered across all of them
+    gates: Vec<JsonValue>,
+    connections: Vec<(usize, usize)>,
+    // the top left of the locations of the gates, which goes wherever they are pasted
+    origin: (f32, f32),
+}
+
+// adds a gate with new nodes to the toplevel (if parent is None) or to a circuit, after all of the gates that are already there so that the paths of the other gates do not change
+pub fn add_gate(simulation: &mut Simulation, parent: Option<CircuitKey>, new_gate: NewGate, location: (f32, f32), direction: GateDirection) -> GateKey {
+    let nodes = &mut simulation.nodes;
+    let gate = match new_gate {
+        NewGate::Nand => simulation.gates.insert_with_key(|gk| Gate::Nand { logic: logic::NandLogic::new(nodes, gk), location: location.into(), direction }),
+        NewGate::Const(value) => simulation.gates.insert_with_key(|gk| Gate::Const { logic: logic::ConstLogic::new(nodes, gk, value), location: location.into(), direction }),
+        NewGate::Unerror => simulation.gates.insert_with_key(|gk| Gate::Unerror { logic: logic::UnerrorLogic::new(nodes, gk), location: location.into(), direction }),
+        NewGate::Button => simulation.gates.insert_with_key(|gk| Gate::Button { logic: logic::ButtonLogic::new(nodes, gk), location: location.into(), direction }),
+        NewGate::TristateBuffer => simulation.gates.insert_with_key(|gk| Gate::TristateBuffer { logic: logic::TristateBufferLogic::new(nodes, gk), location: location.into(), direction }),
+        // copied in the same way as copying and pasting the gate of the circuit, which brings everything inside of it and the connections between them along
+        NewGate::CopyOf(original) => {
+            let original_gate = simulation.gates.iter().find_map(|(gate, kind)| matches!(kind, Gate::Custom(ck) if *ck == original).then_some(gate)).expect("circuit being copied has no gate");
+            let copied = copy_gates(simulation, &[original_gate]);
+            let [gate] = paste_gates(simulation, parent, &copied, location)[..] else { unreachable!("pasting one gate gave a different number of gates") };
+            *Gate::direction_mut(&mut simulation.circuits, &mut simulation.gates, gate) = direction;
+            return gate;
+        }
+    };
+
+    match parent {
+        Some(ck) => simulation.circuits[ck].gates.add_gate(gate),
+        None => simulation.toplevel_gates.add_gate(gate),
+    }
+
+    schedule_gates_in(simulation, gate);
+    gate
+}
+
+// removes a gate together with every gate nested inside of it, all of their nodes, and every connection to those nodes
+// the gates after it in its parent move down by one, so their paths change (see LayoutOverlay::gate_removed)
+// gives back None without changing anything if the gate is not in the simulation
+pub fn remove_gate(simulation: &mut Simulation, gate: GateKey) -> Option<RemovedGate> {
+    let path = simulation.gate_path(gate)?;
+    let gates = gates_in(simulation, gate);
+    // this numbers every node inside of the gate
+    let mut node_numbers = HashMap::new();
+    let gate_json = export::serialize_gate(simulation, &mut node_numbers, gate);
+    let nodes: HashSet<NodeKey> = node_numbers.keys().copied().collect();
+
+    // disconnecting first splits every net that has removed nodes in it so that the nodes that stay are not in a net with nodes that do not exist anymore
+    // this also schedules the nets that are left so that they lose whatever the removed gates were producing
+    let touching: Vec<_> = simulation.connections.iter().filter(|(a, b)| nodes.contains(a) || nodes.contains(b)).copied().collect();
+    let inner_connections = touching.iter().filter_map(|(a, b)| Some((*node_numbers.get(a)?, *node_numbers.get(b)?))).collect();
+    let outer_connections: Vec<_> = touching
+        .iter()
+        .filter_map(|(a, b)| match (node_numbers.get(a), node_numbers.get(b)) {
+            (Some(number), None) => Some((*number, *b)),
+            (None, Some(number)) => Some((*number, *a)),
+            _ => None,
+        })
+        .collect();
+    for (a, b) in touching {
+        simulation.disconnect(a, b);
+    }
+
+    if !simulation.toplevel_gates.remove_gate(gate) {
+        for circuit in simulation.circuits.values_mut() {
+            if circuit.gates.remove_gate(gate) {
+                break;
+            }
+        }
+    }
+
+    // the scheduler can still have these queued, but update skips anything that does not exist anymore
+    for gate in gates {
+        if let Some(Gate::Custom(ck)) = simulation.gates.remove(gate) {
+            simulation.circuits.remove(ck);
+        }
+    }
+    for node in nodes {
+        simulation.nodes.remove(node);
+    }
+
+    let outer_connections = outer_connections.into_iter().filter_map(|(number, node)| Some((number, simulation.node_path(node)?))).collect();
+    Some(RemovedGate { path, gate: gate_json, inner_connections, outer_connections })
+}
+
+// puts a removed gate back at the same path that it had, which only works if the simulation is the same as right after it was removed
+// the gate gets new keys for itself and everything inside of it
+pub fn restore_gate(simulation: &mut Simulation, removed: &RemovedGate) -> Option<GateKey> {
+    let (parent, index) = parent_and_index(simulation, &removed.path)?;
+    let num_siblings = match parent {
+        Some(ck) => simulation.circuits[ck].gates.iter().len(),
+        None => simulation.toplevel_gates.iter().len(),
+    };
+    if index > num_siblings {
+        return None;
+    }
+    let outer_connections = removed.outer_connections.iter().map(|(number, node)| Some((*number, simulation.find_node(node)?))).collect::<Option<Vec<_>>>()?;
+
+    let mut node_mapping = HashMap::new();
+    let gate = import::parse_gate(&mut simulation.circuits, &mut simulation.gates, &mut simulation.nodes, &mut node_mapping, removed.gate.clone()).expect("removed gate could not be read back");
+    match parent {
+        Some(ck) => simulation.circuits[ck].gates.insert_gate(index, gate),
+        None => simulation.toplevel_gates.insert_gate(index, gate),
+    }
+
+    for (a, b) in &removed.inner_connections {
+        connections::connect(&mut simulation.connections, &mut simulation.nodes, node_mapping[a], node_mapping[b]);
+    }
+    for (number, node) in outer_connections {
+        connections::connect(&mut simulation.connections, &mut simulation.nodes, node_mapping[&number], node);
+    }
+
+    schedule_gates_in(simulation, gate);
+    Some(gate)
+}
+
+// the connections between the gates are copied too, but not the connections to anything else
+pub fn copy_gates(simulation: &Simulation, gates: &[GateKey]) -> CopiedGates {
+    let mut node_numbers = HashMap::new();
+    let gate_jsons = gates.iter().map(|gate| export::serialize_gate(simulation, &mut node_numbers, *gate)).collect();
+    let connections = simulation.connections.iter().filter_map(|(a, b)| Some((*node_numbers.get(a)?, *node_numbers.get(b)?))).collect();
+    let origin = gates
+        .iter()
+        .map(|gate| Gate::location(&simulation.circuits, &simulation.gates, *gate))
+        .fold((f32::INFINITY, f32::INFINITY), |(x, y), location| (x.min(location.x), y.min(location.y)));
+    CopiedGates { gates: gate_jsons, connections, origin }
+}
+
+// adds copies of the gates to the toplevel or a circuit (after everything that is already there, like add_gate) with new keys for everything in them
+// they are moved so that the top left of them is at location
+pub fn paste_gates(simulation: &mut Simulation, parent: Option<CircuitKey>, copied: &CopiedGates, location: (f32, f32)) -> Vec<GateKey> {
+    let mut node_mapping = HashMap::new();
+    let gates: Vec<_> = copied
+        .gates
+        .iter()
+        .map(|gate| import::parse_gate(&mut simulation.circuits, &mut simulation.gates, &mut simulation.nodes, &mut node_mapping, gate.clone()).expect("copied gate could not be read back"))
+        .collect();
+
+    for (a, b) in &copied.connections {
+        connections::connect(&mut simulation.connections, &mut simulation.nodes, node_mapping[a], node_mapping[b]);
+    }
+    for gate in &gates {
+        let gate_location = Gate::location_mut(&mut simulation.circuits, &mut simulation.gates, *gate);
+        gate_location.x += location.0 - copied.origin.0;
+        gate_location.y += location.1 - copied.origin.1;
+        match parent {
+            Some(ck) => simulation.circuits[ck].gates.add_gate(*gate),
+            None => simulation.toplevel_gates.add_gate(*gate),
+        }
+        schedule_gates_in(simulation, *gate);
+    }
+
+    gates
+}
+
+// moves gates that are all in the same parent into a new subcircuit, which is added to the parent after the gates that are left there
+// the inputs of the subcircuit are the nodes outside that the gates are connected to through their inputs
+// and the outputs are the outputs of the gates that are connected to anything outside, so every net stays the same
+// the gates keep their keys, and their locations become relative to the subcircuit (which goes where the top left of them was) so that inline_gate puts them back where they were
+pub fn extract_gates(simulation: &mut Simulation, gates: &[GateKey]) -> Option<(GateKey, ExtractedGates)> {
+    let (parent, _) = position_in_parent(simulation, *gates.first()?)?;
+    let mut indexed_gates = Vec::new();
+    for gate in gates {
+        let (gate_parent, index) = position_in_parent(simulation, *gate)?;
+        if gate_parent != parent {
+            return None;
+        }
+        indexed_gates.push((index, *gate));
+    }
+    indexed_gates.sort_by_key(|(index, _)| *index);
+    indexed_gates.dedup();
+
+    let inside_nodes: HashSet<NodeKey> = indexed_gates
+        .iter()
+        .flat_map(|(_, gate)| gates_in(simulation, *gate))
+        .flat_map(|gate| Gate::inputs(&simulation.circuits, &simulation.gates, gate).iter().chain(Gate::outputs(&simulation.circuits, &simulation.gates, gate)).copied().collect::<Vec<_>>())
+        .collect();
+    // sorted by path so that extracting the same gates again (when redoing) makes the same subcircuit
+    let mut crossing: Vec<_> = simulation
+        .connections
+        .iter()
+        .filter_map(|(a, b)| match (inside_nodes.contains(a), inside_nodes.contains(b)) {
+            (true, false) => Some((*a, *b)),
+            (false, true) => Some((*b, *a)),
+            _ => None,
+        })
+        .collect();
+    crossing.sort_by_cached_key(|(inside, outside)| (simulation.node_path(*inside), simulation.node_path(*outside)));
+
+    // (outside node, inside nodes) for every input and (inside node, outside nodes) for every output
+    let mut inputs: Vec<(NodeKey, Vec<NodeKey>)> = Vec::new();
+    let mut outputs: Vec<(NodeKey, Vec<NodeKey>)> = Vec::new();
+    for (inside, outside) in &crossing {
+        match simulation.nodes[*inside].parent.kind() {
+            hierarchy::NodeParentKind::GateIn(_, _) | hierarchy::NodeParentKind::CircuitIn(_, _) => match inputs.iter_mut().find(|(node, _)| node == outside) {
+                Some((_, inside_nodes)) => inside_nodes.push(*inside),
+                None => inputs.push((*outside, vec![*inside])),
+            },
+            hierarchy::NodeParentKind::GateOut(_, _) | hierarchy::NodeParentKind::CircuitOut(_, _) => match outputs.iter_mut().find(|(node, _)| node == inside) {
+                Some((_, outside_nodes)) => outside_nodes.push(*outside),
+                None => outputs.push((*inside, vec![*outside])),
+            },
+        }
+    }
+
+    let location = indexed_gates
+        .iter()
+        .map(|(_, gate)| Gate::location(&simulation.circuits, &simulation.gates, *gate))
+        .fold((f32::INFINITY, f32::INFINITY), |(x, y), location| (x.min(location.x), y.min(location.y)));
+    let name = (1..).map(|number| format!("subcircuit {number}")).find(|name| simulation.circuits.values().all(|circuit| circuit.name != *name)).expect("ran out of subcircuit names");
+    let ck = simulation.circuits.insert_with_key(|ck| Circuit::new(ck, &mut simulation.nodes, name, location.into(), GateDirection::LTR, inputs.len(), outputs.len()));
+    let (boundary_inputs, boundary_outputs) = (simulation.circuits[ck].nodes.inputs().clone(), simulation.circuits[ck].nodes.outputs().clone());
+
+    for (inside, outside) in crossing {
+        simulation.disconnect(inside, outside);
+    }
+    for ((outside, inside_nodes), boundary) in inputs.into_iter().zip(boundary_inputs) {
+        simulation.connect(outside, boundary);
+        for inside in inside_nodes {
+            simulation.connect(boundary, inside);
+        }
+    }
+    for ((inside, outside_nodes), boundary) in outputs.into_iter().zip(boundary_outputs) {
+        simulation.connect(inside, boundary);
+        for outside in outside_nodes {
+            simulation.connect(boundary, outside);
+        }
+    }
+
+    let mut locations = Vec::new();
+    for (_, gate) in &indexed_gates {
+        let gate_location = Gate::location_mut(&mut simulation.circuits, &mut simulation.gates, *gate);
+        locations.push((gate_location.x, gate_location.y));
+        gate_location.x -= location.0;
+        gate_location.y -= location.1;
+        match parent {
+            Some(parent) => simulation.circuits[parent].gates.remove_gate(*gate),
+            None => simulation.toplevel_gates.remove_gate(*gate),
+        };
+        simulation.circuits[ck].gates.add_gate(*gate);
+    }
+    let gate = simulation.gates.insert(Gate::Custom(ck));
+    match parent {
+        Some(parent) => simulation.circuits[parent].gates.add_gate(gate),
+        None => simulation.toplevel_gates.add_gate(gate),
+    }
+
+    Some((gate, ExtractedGates { indices: indexed_gates.into_iter().map(|(index, _)| index).collect(), locations }))
+}
+
+// undoes extract_gates by moving the gates in the subcircuit back to where they were and removing the subcircuit
+pub fn put_back_extracted(simulation: &mut Simulation, gate: GateKey, extracted: &ExtractedGates) -> Option<Vec<GateKey>> {
+    if extracted.locations.len() != extracted.indices.len() {
+        return None;
+    }
+    let subgates = dissolve_subcircuit(simulation, gate, &extracted.indices, &mut Vec::new())?;
+    // the locations are put back exactly instead of adding the location of the subcircuit back so that undoing does not get rounded differently
+    for (subgate, location) in subgates.iter().zip(&extracted.locations) {
+        *Gate::location_mut(&mut simulation.circuits, &mut simulation.gates, *subgate) = (*location).into();
+    }
+    Some(subgates)
+}
+
+// replaces a subcircuit with the gates inside of it, which go where it was in its parent and keep their keys
+// they are moved by the location of the subcircuit because their locations were relative to it
+pub fn inline_gate(simulation: &mut Simulation, gate: GateKey) -> Option<InlinedGate> {
+    let Some(&Gate::Custom(ck)) = simulation.gates.get(gate) else { return None };
+    let path = simulation.gate_path(gate)?;
+    let (_, index) = position_in_parent(simulation, gate)?;
+    let circuit = &simulation.circuits[ck];
+    let (input_names, output_names) = (circuit.nodes.input_names().clone(), circuit.nodes.output_names().clone());
+    let (num_inputs, num_outputs) = (circuit.nodes.inputs().len(), circuit.nodes.outputs().len());
+    let (name, location, direction) = (circuit.name.clone(), (circuit.location.x, circuit.location.y), circuit.direction);
+    let subgates: Vec<_> = circuit.gates.iter().copied().collect();
+    let locations: Vec<_> = subgates.iter().map(|subgate| Gate::location(&simulation.circuits, &simulation.gates, *subgate)).map(|location| (location.x, location.y)).collect();
+
+    let boundary: HashSet<NodeKey> = circuit.nodes.inputs().iter().chain(circuit.nodes.outputs()).copied().collect();
+    let boundary_connections = simulation
+        .connections
+        .iter()
+        .filter(|(a, b)| boundary.contains(a) || boundary.contains(b))
+        .map(|(a, b)| Some((simulation.node_path(*a)?, simulation.node_path(*b)?)))
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut spliced_connections = Vec::new();
+    let subgates = dissolve_subcircuit(simulation, gate, &(index..index + subgates.len()).collect::<Vec<_>>(), &mut spliced_connections)?;
+    for subgate in &subgates {
+        let subgate_location = Gate::location_mut(&mut simulation.circuits, &mut simulation.gates, *subgate);
+        subgate_location.x += location.0;
+        subgate_location.y += location.1;
+    }
+    let spliced_connections = spliced_connections.into_iter().filter_map(|(a, b)| Some((simulation.node_path(a)?, simulation.node_path(b)?))).collect();
+
+    Some(InlinedGate { path, name, input_names, output_names, num_inputs, num_outputs, location, direction, locations, boundary_connections, spliced_connections })
+}
+
+// undoes inline_gate by putting the gates back into a subcircuit that is the same as the one that they were in, which only works if the simulation is the same as right after inlining
+pub fn restore_inlined(simulation: &mut Simulation, inlined: &InlinedGate) -> Option<GateKey> {
+    let (parent, index) = parent_and_index(simulation, &inlined.path)?;
+    let siblings: Vec<_> = match parent {
+        Some(parent) => simulation.circuits[parent].gates.iter().copied().collect(),
+        None => simulation.toplevel_gates.iter().copied().collect(),
+    };
+    let subgates = siblings.get(index..index + inlined.locations.len())?.to_vec();
+    let spliced_connections = inlined.spliced_connections.iter().map(|(a, b)| Some((simulation.find_node(a)?, simulation.find_node(b)?))).collect::<Option<Vec<_>>>()?;
+
+    for (a, b) in spliced_connections {
+        simulation.disconnect(a, b);
+    }
+    let ck = simulation
+        .circuits
+        .insert_with_key(|ck| Circuit::new(ck, &mut simulation.nodes, inlined.name.clone(), inlined.location.into(), inlined.direction, inlined.num_inputs, inlined.num_outputs));
+    simulation.circuits[ck].nodes.set_names(inlined.input_names.clone(), inlined.output_names.clone());
+    for (subgate, location) in subgates.iter().zip(&inlined.locations) {
+        match parent {
+            Some(parent) => simulation.circuits[parent].gates.remove_gate(*subgate),
+            None => simulation.toplevel_gates.remove_gate(*subgate),
+        };
+        simulation.circuits[ck].gates.add_gate(*subgate);
+        *Gate::location_mut(&mut simulation.circuits, &mut simulation.gates, *subgate) = (*location).into();
+    }
+    let gate = simulation.gates.insert(Gate::Custom(ck));
+    match parent {
+        Some(parent) => simulation.circuits[parent].gates.insert_gate(index, gate),
+        None => simulation.toplevel_gates.insert_gate(index, gate),
+    }
+
+    // the paths of these are only right now that the subcircuit is back
+    for (a, b) in &inlined.boundary_connections {
+        if let (Some(a), Some(b)) = (simulation.find_node(a), simulation.find_node(b)) {
+            simulation.connect(a, b);
+        }
+    }
+    Some(gate)
+}
+
+// moves the gates in a subcircuit to the indices in its parent and removes the subcircuit along with its inputs and outputs
+// every input and output is replaced by connecting the nodes inside that it was connected to with the nodes outside that it was connected to, so every net stays the same
+// spliced gets the connections that were made for that
+fn dissolve_subcircuit(simulation: &mut Simulation, gate: GateKey, indices: &[usize], spliced: &mut Vec<(NodeKey, NodeKey)>) -> Option<Vec<GateKey>> {
+    let Some(&Gate::Custom(ck)) = simulation.gates.get(gate) else { return None };
+    let (parent, _) = position_in_parent(simulation, gate)?;
+    let subgates: Vec<_> = simulation.circuits[ck].gates.iter().copied().collect();
+    let num_siblings = match parent {
+        Some(parent) => simulation.circuits[parent].gates.iter().len(),
+        None => simulation.toplevel_gates.iter().len(),
+    };
+    // the indices are in order, so every gate goes either before or right after the gates that were put back before it
+    if subgates.len() != indices.len() || indices.iter().enumerate().any(|(i, index)| *index > num_siblings - 1 + i || (i > 0 && *index <= indices[i - 1])) {
+        return None;
+    }
+
+    let inside_nodes: HashSet<NodeKey> = subgates
+        .iter()
+        .flat_map(|subgate| gates_in(simulation, *subgate))
+        .flat_map(|subgate| Gate::inputs(&simulation.circuits, &simulation.gates, subgate).iter().chain(Gate::outputs(&simulation.circuits, &simulation.gates, subgate)).copied().collect::<Vec<_>>())
+        .collect();
+    let boundary: Vec<_> = simulation.circuits[ck].nodes.inputs().iter().chain(simulation.circuits[ck].nodes.outputs()).copied().collect();
+    for node in &boundary {
+        let (inside, outside): (Vec<_>, Vec<_>) = simulation.nodes[*node].connections.adjacent().iter().copied().partition(|adjacent| inside_nodes.contains(adjacent));
+        for adjacent in inside.iter().chain(&outside) {
+            simulation.disconnect(*node, *adjacent);
+        }
+        // an input that goes straight to an output inside or a node that is only connected on one side still has to keep everything that it was connected to in one net
+        // sorted by path so that doing this again (when redoing) connects them the same way
+        let replacements: Vec<_> = if inside.is_empty() || outside.is_empty() {
+            let mut adjacent: Vec<_> = inside.into_iter().chain(outside).collect();
+            adjacent.sort_by_cached_key(|adjacent| simulation.node_path(*adjacent));
+            adjacent.iter().skip(1).map(|other| (adjacent[0], *other)).collect()
+        } else {
+            inside.iter().flat_map(|inside| outside.iter().map(|outside| (*inside, *outside))).collect()
+        };
+        for (a, b) in replacements {
+            if !simulation.connections.contains(a, b) {
+                simulation.connect(a, b);
+                spliced.push((a, b));
+            }
+        }
+    }
+
+    match parent {
+        Some(parent) => simulation.circuits[parent].gates.remove_gate(gate),
+        None => simulation.toplevel_gates.remove_gate(gate),
+    };
+    simulation.gates.remove(gate);
+    simulation.circuits.remove(ck);
+    for node in boundary {
+        simulation.nodes.remove(node);
+    }
+    for (subgate, index) in subgates.iter().zip(indices) {
+        match parent {
+            Some(parent) => simulation.circuits[parent].gates.insert_gate(*index, *subgate),
+            None => simulation.toplevel_gates.insert_gate(*index, *subgate),
+        }
+    }
+
+    // connections to inputs or outputs that were spliced later are gone along with them
+    spliced.retain(|(a, b)| simulation.connections.contains(*a, *b));
+    Some(subgates)
+}
+
+// one circuit for every different subcircuit in the simulation (the first one that has each name), sorted by name
+pub fn circuit_definitions(simulation: &Simulation) -> Vec<CircuitKey> {
+    let mut seen = HashSet::new();
+    let mut definitions: Vec<_> = simulation.circuits.iter().filter(|(_, circuit)| seen.insert(circuit.name.as_str())).map(|(ck, _)| ck).collect();
+    definitions.sort_by(|a, b| simulation.circuits[*a].name.cmp(&simulation.circuits[*b].name));
+    definitions
+}
+
+// a gate and every gate nested inside of it
+pub fn gates_in(simulation: &Simulation, gate: GateKey) -> Vec<GateKey> {
+    let mut gates = vec![gate];
+    if let Gate::Custom(ck) = simulation.gates[gate] {
+        for subgate in simulation.circuits[ck].gates.iter() {
+            gates.extend(gates_in(simulation, *subgate));
+        }
+    }
+    gates
+}
+
+// the circuit that a gate is directly inside of (None for the toplevel) and the index of the gate in it
+fn position_in_parent(simulation: &Simulation, gate: GateKey) -> Option<(Option<CircuitKey>, usize)> {
+    if let Some(index) = simulation.toplevel_gates.iter().position(|child| *child == gate) {
+        return Some((None, index));
+    }
+    simulation.circuits.iter().find_map(|(ck, circuit)| Some((Some(ck), circuit.gates.iter().position(|child| *child == gate)?)))
+}
+
+// the circuit that the gate at a path is in (None for the toplevel) and the index in it, which are found from the path alone so that the gate does not have to exist
+fn parent_and_index(simulation: &Simulation, gate_path: &str) -> Option<(Option<CircuitKey>, usize)> {
+    let (parent_path, segment) = gate_path.rsplit_once('/')?;
+    let (_, index) = path::parse_segment(segment)?;
+    if parent_path == path::TOPLEVEL_NAME {
+        return Some((None, index));
+    }
+    let Gate::Custom(ck) = simulation.gates[simulation.find_gate(parent_path)?] else { return None };
+    Some((Some(ck), index))
+}
+
+// so that a gate that was just put into the simulation and everything connected to it settle in the next update
+fn schedule_gates_in(simulation: &mut Simulation, gate: GateKey) {
+    for gate in gates_in(simulation, gate) {
+        simulation.scheduler.schedule_gate(gate);
+        for node in Gate::inputs(&simulation.circuits, &simulation.gates, gate).iter().chain(Gate::outputs(&simulation.circuits, &simulation.gates, gate)) {
+            simulation.scheduler.schedule_net(*node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        export, import,
+        simulation::{
+            hierarchy::NodeParentKind,
+            tests::{assert_same_behaviour, PROJECT},
+            Gate, Simulation,
+        },
+    };
+
+    // every key that something in the simulation points to exists, and every gate that exists is somewhere in the hierarchy
+    fn assert_no_dangling_keys(simulation: &Simulation) {
+        let children: Vec<_> = simulation.toplevel_gates.iter().chain(simulation.circuits.values().flat_map(|circuit| circuit.gates.iter())).copied().collect();
+        assert!(children.iter().all(|gate| simulation.gates.contains_key(*gate)), "a circuit has a gate that does not exist");
+        assert_eq!(children.len(), simulation.gates.len(), "a gate is not in any circuit");
+
+        for (gate, _) in simulation.gates.iter() {
+            if let Gate::Custom(ck) = simulation.gates[gate] {
+                assert!(simulation.circuits.contains_key(ck), "a gate has a circuit that does not exist");
+            }
+            for node in Gate::inputs(&simulation.circuits, &simulation.gates, gate).iter().chain(Gate::outputs(&simulation.circuits, &simulation.gates, gate)) {
+                assert!(simulation.nodes.contains_key(*node), "a gate has a node that does not exist");
+            }
+        }
+        for (a, b) in simulation.connections.iter() {
+            assert!(simulation.nodes.contains_key(*a) && simulation.nodes.contains_key(*b), "a connection goes to a node that does not exist");
+        }
+        for (node, node_data) in simulation.nodes.iter() {
+            assert!(node_data.connections.adjacent().iter().all(|adjacent| simulation.nodes.contains_key(*adjacent)), "a node is connected to a node that does not exist");
+            assert!(super::connections::net_members(&simulation.nodes, node).iter().all(|member| simulation.nodes.contains_key(*member)), "a net has a node that does not exist");
+            let parent_exists = match node_data.parent.kind() {
+                NodeParentKind::GateIn(gate, _) | NodeParentKind::GateOut(gate, _) => simulation.gates.contains_key(gate),
+                NodeParentKind::CircuitIn(ck, _) | NodeParentKind::CircuitOut(ck, _) => simulation.circuits.contains_key(ck),
+            };
+            assert!(parent_exists, "a node belongs to something that does not exist");
+        }
+    }
+
+    #[test]
+    fn copy_of_circuit_is_the_same_as_its_gate() {
+        let mut simulation = import::import(PROJECT).unwrap();
+        let original = simulation.find_gate("main/adder4[8]").unwrap();
+        let Gate::Custom(ck) = simulation.gates[original] else { panic!("adder4 is not a subcircuit") };
+        let location = Gate::location(&simulation.circuits, &simulation.gates, original);
+        let (location, direction) = ((location.x, location.y), Gate::direction(&simulation.circuits, &simulation.gates, original));
+        let copy = super::add_gate(&mut simulation, None, super::NewGate::CopyOf(ck), location, direction);
+        assert_no_dangling_keys(&simulation);
+
+        // numbered separately so that the node numbers start from the same place, which makes the connections inside of them the same pairs of numbers
+        let serialize = |gate| {
+            let mut node_numbers = HashMap::new();
+            let json = export::serialize_gate(&simulation, &mut node_numbers, gate);
+            let mut connections: Vec<_> = simulation.connections.iter().filter_map(|(a, b)| Some((node_numbers.get(a)?, node_numbers.get(b)?))).map(|(a, b)| (*a.min(b), *a.max(b))).collect();
+            connections.sort_unstable();
+            (json, connections)
+        };
+        let (copy, original) = (serialize(copy), serialize(original));
+        assert!(!original.1.is_empty());
+        assert_eq!(copy, original);
+    }
+
+    #[test]
+    fn remove_then_restore_every_gate() {
+        let original = import::import(PROJECT).unwrap();
+        // importing the same file again gives the same keys, so the keys from original can be used in the copies
+        for (gate, _) in original.gates.iter() {
+            let mut simulation = import::import(PROJECT).unwrap();
+            let path = simulation.gate_path(gate).unwrap();
+            let removed = super::remove_gate(&mut simulation, gate).unwrap();
+            assert_no_dangling_keys(&simulation);
+            assert!(!simulation.gates.contains_key(gate), "{path} is still there after removing it");
+            assert!(super::gates_in(&original, gate).iter().all(|inside| !simulation.gates.contains_key(*inside)), "something inside of {path} is still there after removing it");
+
+            let restored = super::restore_gate(&mut simulation, &removed).unwrap();
+            assert_no_dangling_keys(&simulation);
+            assert_eq!(simulation.gate_path(restored).as_deref(), Some(path.as_str()));
+            // the export has every gate at its path and every connection between the paths of their nodes
+            assert_eq!(export::export_to_json(&simulation), export::export_to_json(&original), "restoring {path} did not give back the same simulation");
+        }
+    }
+
+    #[test]
+    fn stale_keys_change_nothing() {
+        let mut simulation = import::import(PROJECT).unwrap();
+        let adder4 = simulation.find_gate("main/adder4[8]").unwrap();
+        let (subcircuit, extracted) = super::extract_gates(&mut simulation, &[adder4]).unwrap();
+        super::put_back_extracted(&mut simulation, subcircuit, &extracted).unwrap();
+        super::remove_gate(&mut simulation, adder4).unwrap();
+        let after_removing = export::export_to_json(&simulation);
+
+        // adder4 and the subcircuit that it was extracted into are both gone now
+        for gate in [adder4, subcircuit] {
+            assert!(super::remove_gate(&mut simulation, gate).is_none());
+            assert!(super::inline_gate(&mut simulation, gate).is_none());
+            assert!(super::put_back_extracted(&mut simulation, gate, &extracted).is_none());
+            assert!(super::extract_gates(&mut simulation, &[gate]).is_none());
+        }
+        assert_no_dangling_keys(&simulation);
+        assert_eq!(export::export_to_json(&simulation), after_removing);
+    }
+
+    #[test]
+    fn extract_then_put_back() {
+        let original = import::import(PROJECT).unwrap();
+        let adder4 = original.find_gate("main/adder4[8]").unwrap();
+        let Gate::Custom(adder4_circuit) = original.gates[adder4] else { panic!("adder4 is not a subcircuit") };
+        let toplevel: Vec<_> = original.toplevel_gates.iter().copied().collect();
+        let selections = [
+            // buttons and the subcircuit that they drive, which are not next to each other
+            vec![toplevel[0], toplevel[2], adder4],
+            // gates inside of a subcircuit, where some of their inputs come from the inputs of that subcircuit
+            original.circuits[adder4_circuit].gates.iter().copied().skip(1).take(2).collect(),
+        ];
+
+        for (seed, gates) in selections.into_iter().enumerate() {
+            let mut unedited = import::import(PROJECT).unwrap();
+            let mut simulation = import::import(PROJECT).unwrap();
+            let (subcircuit, extracted) = super::extract_gates(&mut simulation, &gates).unwrap();
+            assert_no_dangling_keys(&simulation);
+            let Gate::Custom(ck) = simulation.gates[subcircuit] else { panic!("extracting did not make a subcircuit") };
+            assert!(simulation.circuits[ck].gates.iter().eq(&gates), "the subcircuit does not have the extracted gates in order");
+            assert_same_behaviour(&mut unedited, &mut simulation, 200, Some(seed as u32), |_, _, _| {});
+
+            assert!(super::put_back_extracted(&mut simulation, subcircuit, &extracted).unwrap() == gates);
+            assert_no_dangling_keys(&simulation);
+            assert_eq!(export::export_to_json(&simulation), export::export_to_json(&original), "putting back the extracted gates did not give back the same simulation");
+            assert_same_behaviour(&mut unedited, &mut simulation, 200, Some(seed as u32 + 100), |_, _, _| {});
+        }
+    }
+
+    #[test]
+    fn extract_group_with_nothing_going_out() {
+        let original = import::import(PROJECT).unwrap();
+        let mut unedited = import::import(PROJECT).unwrap();
+        let mut simulation = import::import(PROJECT).unwrap();
+        // the toplevel has no inputs or outputs, so nothing connects everything in it to anything else
+        let gates: Vec<_> = simulation.toplevel_gates.iter().copied().collect();
+        let (subcircuit, extracted) = super::extract_gates(&mut simulation, &gates).unwrap();
+        assert_no_dangling_keys(&simulation);
+        assert_eq!(Gate::num_inputs(&simulation.circuits, &simulation.gates, subcircuit), 0);
+        assert_eq!(Gate::num_outputs(&simulation.circuits, &simulation.gates, subcircuit), 0);
+        assert_same_behaviour(&mut unedited, &mut simulation, 200, Some(7), |_, _, _| {});
+
+        assert!(super::put_back_extracted(&mut simulation, subcircuit, &extracted).unwrap() == gates);
+        assert_no_dangling_keys(&simulation);
+        assert_eq!(export::export_to_json(&simulation), export::export_to_json(&original));
+    }
+
+    #[test]
+    fn inline_then_restore_every_subcircuit() {
+        let original = import::import(PROJECT).unwrap();
+        let subcircuits: Vec<_> = original.gates.iter().filter(|(_, gate)| matches!(gate, Gate::Custom(_))).map(|(gate, _)| gate).collect();
+        assert!(subcircuits.len() > 1);
+
+        for (seed, gate) in subcircuits.into_iter().enumerate() {
+            let mut unedited = import::import(PROJECT).unwrap();
+            let mut simulation = import::import(PROJECT).unwrap();
+            let path = simulation.gate_path(gate).unwrap();
+            let Gate::Custom(ck) = simulation.gates[gate] else { unreachable!() };
+            let subgates: Vec<_> = simulation.circuits[ck].gates.iter().copied().collect();
+
+            let inlined = super::inline_gate(&mut simulation, gate).unwrap();
+            assert_no_dangling_keys(&simulation);
+            assert!(!simulation.gates.contains_key(gate), "{path} is still there after inlining it");
+            // the first gate that was inside takes the place of the subcircuit
+            assert_eq!(super::position_in_parent(&simulation, subgates[0]), super::position_in_parent(&original, gate));
+            assert_same_behaviour(&mut unedited, &mut simulation, 200, Some(seed as u32), |_, _, _| {});
+
+            let restored = super::restore_inlined(&mut simulation, &inlined).unwrap();
+            assert_no_dangling_keys(&simulation);
+            assert_eq!(simulation.gate_path(restored).as_deref(), Some(path.as_str()));
+            assert_eq!(export::export_to_json(&simulation), export::export_to_json(&original), "restoring {path} after inlining it did not give back the same simulation");
+            assert_same_behaviour(&mut unedited, &mut simulation, 200, Some(seed as u32 + 100), |_, _, _| {});
+        }
+    }
+}
@@ -1,5 +1,5 @@
 mod gate_children;
 mod node_children;
 
-pub(crate) use gate_children::*;
-pub(crate) use node_children::*;
+pub use gate_children::*;
+pub use node_children::*;
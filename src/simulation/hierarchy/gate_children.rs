@@ -1,21 +1,40 @@
 use crate::simulation::GateKey;
 
-pub(crate) struct GateChildren {
+pub struct GateChildren {
     gates: Vec<GateKey>,
 }
 
 impl GateChildren {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self { gates: Vec::new() }
     }
 
-    pub(crate) fn iter(&self) -> std::slice::Iter<GateKey> {
+    pub fn iter(&self) -> std::slice::Iter<'_, GateKey> {
         self.gates.iter()
     }
 
-    pub(crate) fn add_gate(&mut self, gate: GateKey) {
+    pub fn get(&self, index: usize) -> Option<GateKey> {
+        self.gates.get(index).copied()
+    }
+
+    pub fn add_gate(&mut self, gate: GateKey) {
         self.gates.push(gate);
     }
+
+    pub fn insert_gate(&mut self, index: usize, gate: GateKey) {
+        self.gates.insert(index, gate);
+    }
+
+    // returns whether the gate was one of these children
+    pub fn remove_gate(&mut self, gate: GateKey) -> bool {
+        match self.gates.iter().position(|child| *child == gate) {
+            Some(index) => {
+                self.gates.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 impl<'a> IntoIterator for &'a GateChildren {
@@ -1,17 +1,35 @@
 use crate::simulation::{connections, logic, CircuitKey, GateKey, Node, NodeKey, NodeMap};
 
-pub(crate) struct NodeChildren<I: private::NodeVec, O: private::NodeVec> {
+pub struct NodeChildren<I: private::NodeVec, O: private::NodeVec> {
     inputs: I,
     outputs: O,
+
+    input_names: PortNames,
+    output_names: PortNames,
+}
+
+// names for the inputs or outputs of a gate, which come from the names of the fields of the bundles in the python generator
+// both are optional: gates that were not given names have no names and no bundles
+#[derive(Clone, Default)]
+pub struct PortNames {
+    names: Vec<Option<String>>,
+    bundles: Vec<Bundle>,
+}
+// a group of consecutive nodes that all belong to the same field, like the 4 data bits of a register
+#[derive(Clone)]
+pub struct Bundle {
+    pub name: String,
+    pub start: usize,
+    pub size: usize,
 }
 
 #[derive(Copy, Clone)]
-pub(crate) struct NodeParent {
+pub struct NodeParent {
     kind: NodeParentKind,
 }
 
 #[derive(Copy, Clone)]
-pub(crate) enum NodeParentKind {
+pub enum NodeParentKind {
     GateIn(GateKey, usize),
     GateOut(GateKey, usize),
     CircuitIn(CircuitKey, usize),
@@ -19,7 +37,7 @@ pub(crate) enum NodeParentKind {
 }
 
 #[derive(Copy, Clone)]
-pub(crate) enum NodeParentType {
+pub enum NodeParentType {
     Gate(GateKey),
     Circuit(CircuitKey),
 }
@@ -27,20 +45,20 @@ pub(crate) enum NodeParentType {
 mod private {
     use crate::simulation::NodeKey;
 
-    pub(crate) trait NodeVec {
+    pub trait NodeVec {
         type ExtraData;
         fn from_node_generator(next: impl FnMut() -> NodeKey, extra: Self::ExtraData) -> Self;
     }
 }
 
 impl NodeParent {
-    pub(crate) fn kind(&self) -> NodeParentKind {
+    pub fn kind(&self) -> NodeParentKind {
         self.kind
     }
 }
 
 impl<I: private::NodeVec, O: private::NodeVec> NodeChildren<I, O> {
-    pub(crate) fn new(nodes: &mut NodeMap, parent_type: NodeParentType, i_extra: I::ExtraData, o_extra: O::ExtraData) -> NodeChildren<I, O> {
+    pub fn new(nodes: &mut NodeMap, parent_type: NodeParentType, i_extra: I::ExtraData, o_extra: O::ExtraData) -> NodeChildren<I, O> {
         NodeChildren {
             inputs: I::from_node_generator(
                 {
@@ -84,16 +102,45 @@ impl<I: private::NodeVec, O: private::NodeVec> NodeChildren<I, O> {
                 },
                 o_extra,
             ),
+            input_names: PortNames::default(),
+            output_names: PortNames::default(),
         }
     }
 
-    pub(crate) fn inputs(&self) -> &I {
+    pub fn inputs(&self) -> &I {
         &self.inputs
     }
 
-    pub(crate) fn outputs(&self) -> &O {
+    pub fn outputs(&self) -> &O {
         &self.outputs
     }
+
+    pub fn input_names(&self) -> &PortNames {
+        &self.input_names
+    }
+    pub fn output_names(&self) -> &PortNames {
+        &self.output_names
+    }
+    pub fn set_names(&mut self, input_names: PortNames, output_names: PortNames) {
+        self.input_names = input_names;
+        self.output_names = output_names;
+    }
+}
+
+impl PortNames {
+    pub fn new(names: Vec<Option<String>>, bundles: Vec<Bundle>) -> PortNames {
+        PortNames { names, bundles }
+    }
+
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.names.get(index)?.as_deref()
+    }
+    pub fn bundles(&self) -> &[Bundle] {
+        &self.bundles
+    }
+    pub fn is_empty(&self) -> bool {
+        self.names.iter().all(Option::is_none) && self.bundles.is_empty()
+    }
 }
 
 impl private::NodeVec for Vec<NodeKey> {
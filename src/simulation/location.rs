@@ -1,6 +1,6 @@
-pub(crate) struct GateLocation {
-    pub(crate) x: f32,
-    pub(crate) y: f32,
+pub struct GateLocation {
+    pub x: f32,
+    pub y: f32,
 }
 
 impl From<(f32, f32)> for GateLocation {
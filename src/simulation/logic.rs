@@ -1,35 +1,45 @@
 use std::collections::HashSet;
 
-use crate::simulation::{hierarchy, Gate, GateKey, GateMap, Node, NodeKey, NodeMap};
+use crate::simulation::{connections, hierarchy, Gate, GateKey, GateMap, Node, NodeKey, NodeMap};
 
-pub(crate) struct NodeLogic {
+pub struct NodeLogic {
     production: Option<Value>,
     value: Value,
 }
 
-pub(crate) struct NandLogic {
-    pub(crate) nodes: hierarchy::NodeChildren<[NodeKey; 2], [NodeKey; 1]>,
+pub struct NandLogic {
+    pub nodes: hierarchy::NodeChildren<[NodeKey; 2], [NodeKey; 1]>,
     _dont_construct: (),
 }
-pub(crate) struct ConstLogic {
-    pub(crate) nodes: hierarchy::NodeChildren<[NodeKey; 0], [NodeKey; 1]>,
+pub struct ConstLogic {
+    pub nodes: hierarchy::NodeChildren<[NodeKey; 0], [NodeKey; 1]>,
     name: &'static str,
 }
-pub(crate) struct UnerrorLogic {
-    pub(crate) nodes: hierarchy::NodeChildren<[NodeKey; 1], [NodeKey; 1]>,
+pub struct UnerrorLogic {
+    pub nodes: hierarchy::NodeChildren<[NodeKey; 1], [NodeKey; 1]>,
     _dont_construct: (),
 }
-pub(crate) struct ButtonLogic {
-    pub(crate) nodes: hierarchy::NodeChildren<[NodeKey; 0], [NodeKey; 1]>,
+pub struct ButtonLogic {
+    pub nodes: hierarchy::NodeChildren<[NodeKey; 0], [NodeKey; 1]>,
     _dont_construct: (),
 }
-pub(crate) struct TristateBufferLogic {
-    pub(crate) nodes: hierarchy::NodeChildren<[NodeKey; 2], [NodeKey; 1]>,
+pub struct TristateBufferLogic {
+    pub nodes: hierarchy::NodeChildren<[NodeKey; 2], [NodeKey; 1]>,
     _dont_construct: (),
 }
 
-#[derive(Clone, Copy)]
-pub(crate) enum Value {
+// keeps track of what has to be recomputed in the next update so that the parts of the simulation that are not changing do not need to be looked at
+// anything that changes productions or connections outside of update needs to tell the scheduler about it
+pub struct Scheduler {
+    everything_dirty: bool,
+    // gates that need to be evaluated in the next update, either because the value of one of their inputs changed in the last update or because they are new
+    gate_queue: HashSet<GateKey>,
+    // nets (identified by any node in them, because nets can be merged or split between updates) whose productions changed and that need to be resolved again
+    net_queue: Vec<NodeKey>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Value {
     H,
     L,
     Z,
@@ -49,150 +59,265 @@ impl Value {
     }
 }
 
-// TODO: properly deal with removing gates so that it doesnt panic when gates are removed
-
 impl NodeLogic {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         NodeLogic { production: None, value: Value::Z }
     }
 }
 
 impl NandLogic {
     // default value for the outputs is whatever value results from having all false inputs
-    pub(crate) fn new(nodes: &mut NodeMap, gate_key: GateKey) -> NandLogic {
+    pub fn new(nodes: &mut NodeMap, gate_key: GateKey) -> NandLogic {
         NandLogic { nodes: hierarchy::NodeChildren::new(nodes, hierarchy::NodeParentType::Gate(gate_key), (), ()), _dont_construct: () }
     }
-    pub(crate) fn name(&self) -> &str {
+    pub fn name(&self) -> &str {
         "nand"
     }
 }
 
 impl ConstLogic {
-    pub(crate) fn new(nodes: &mut NodeMap, gate_key: GateKey, value: bool) -> ConstLogic {
+    pub fn new(nodes: &mut NodeMap, gate_key: GateKey, value: bool) -> ConstLogic {
         let gate_nodes: hierarchy::NodeChildren<[NodeKey; 0], [NodeKey; 1]> = hierarchy::NodeChildren::new(nodes, hierarchy::NodeParentType::Gate(gate_key), (), ());
-        set_node_production(nodes, gate_nodes.outputs()[0], if value { Value::H } else { Value::L });
+        nodes[gate_nodes.outputs()[0]].logic.production = Some(if value { Value::H } else { Value::L });
         ConstLogic { nodes: gate_nodes, name: if value { "true" } else { "false" } }
     }
-    pub(crate) fn name(&self) -> &str {
+    pub fn name(&self) -> &str {
         self.name
     }
 }
 
 impl UnerrorLogic {
-    pub(crate) fn new(nodes: &mut NodeMap, gate_key: GateKey) -> UnerrorLogic {
+    pub fn new(nodes: &mut NodeMap, gate_key: GateKey) -> UnerrorLogic {
         UnerrorLogic { nodes: hierarchy::NodeChildren::new(nodes, hierarchy::NodeParentType::Gate(gate_key), (), ()), _dont_construct: () }
     }
-    pub(crate) fn name(&self) -> &str {
+    pub fn name(&self) -> &str {
         "unerror"
     }
 }
 
 impl ButtonLogic {
-    pub(crate) fn new(node_map: &mut NodeMap, gate_key: GateKey) -> ButtonLogic {
+    pub fn new(node_map: &mut NodeMap, gate_key: GateKey) -> ButtonLogic {
         let nodes: hierarchy::NodeChildren<[NodeKey; 0], [NodeKey; 1]> = hierarchy::NodeChildren::new(node_map, hierarchy::NodeParentType::Gate(gate_key), (), ());
-        set_node_production(node_map, nodes.outputs()[0], Value::L);
+        node_map[nodes.outputs()[0]].logic.production = Some(Value::L);
         ButtonLogic { nodes, _dont_construct: () }
     }
-    pub(crate) fn name(&self) -> &str {
+    pub fn name(&self) -> &str {
         "button"
     }
 }
 
 impl TristateBufferLogic {
-    pub(crate) fn new(node_map: &mut NodeMap, gate_key: GateKey) -> TristateBufferLogic {
+    pub fn new(node_map: &mut NodeMap, gate_key: GateKey) -> TristateBufferLogic {
         TristateBufferLogic { nodes: hierarchy::NodeChildren::new(node_map, hierarchy::NodeParentType::Gate(gate_key), (), ()), _dont_construct: () }
     }
-    pub(crate) fn name(&self) -> &str {
+    pub fn name(&self) -> &str {
         "tristate buffer"
     }
 }
 
 // node values {{{1
-pub(crate) fn get_node_value(nodes: &NodeMap, node: NodeKey) -> Value {
+pub fn get_node_value(nodes: &NodeMap, node: NodeKey) -> Value {
     nodes[node].logic.value
 }
-pub(crate) fn get_node_production(nodes: &slotmap::SlotMap<NodeKey, Node>, node: NodeKey) -> Option<Value> {
+pub fn get_node_production(nodes: &slotmap::SlotMap<NodeKey, Node>, node: NodeKey) -> Option<Value> {
     nodes[node].logic.production
 }
 
-pub(crate) fn set_node_production(nodes: &mut NodeMap, index: NodeKey, new_value: Value) {
-    nodes[index].logic.production = Some(new_value);
+pub(crate) fn set_node_production(nodes: &mut NodeMap, scheduler: &mut Scheduler, index: NodeKey, new_value: Value) {
+    if nodes[index].logic.production != Some(new_value) {
+        nodes[index].logic.production = Some(new_value);
+        scheduler.schedule_net(index);
+    }
+}
+// scheduling {{{1
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler { everything_dirty: true, gate_queue: HashSet::new(), net_queue: Vec::new() }
+    }
+
+    pub fn schedule_everything(&mut self) {
+        self.everything_dirty = true;
+    }
+    pub fn schedule_gate(&mut self, gate: GateKey) {
+        self.gate_queue.insert(gate);
+    }
+    pub fn schedule_net(&mut self, node: NodeKey) {
+        self.net_queue.push(node);
+    }
+}
+
+// the gate that reads the value of a node
+// only the inputs of primitive gates are ever read because the nodes of circuits are just passthrough nodes
+fn fan_out(node_map: &NodeMap, node: NodeKey) -> Option<GateKey> {
+    match node_map[node].parent.kind() {
+        hierarchy::NodeParentKind::GateIn(gate, _) => Some(gate),
+        hierarchy::NodeParentKind::GateOut(_, _) | hierarchy::NodeParentKind::CircuitIn(_, _) | hierarchy::NodeParentKind::CircuitOut(_, _) => None,
+    }
 }
 // update {{{1
-pub(crate) fn update(gates: &mut GateMap, node_map: &mut NodeMap) {
-    use std::collections::BTreeMap;
+pub(crate) fn update(gates: &mut GateMap, node_map: &mut NodeMap, scheduler: &mut Scheduler) {
+    if scheduler.everything_dirty {
+        scheduler.everything_dirty = false;
+        scheduler.gate_queue.extend(gates.keys());
+        scheduler.net_queue.extend(node_map.keys());
+    }
+
     // all gates calculate their values based on the values of the nodes in the previous subtick and then all updates get applied all at once
-    let gate_outputs: Vec<(NodeKey, Value)> = gates
-        .iter()
-        .filter_map(|(_, gate)| -> Option<(NodeKey, Value)> {
-            match &gate {
-                Gate::Nand { logic: NandLogic { nodes: logic_nodes, _dont_construct: () }, location: _, direction: _ } => {
-                    let [a, b] = logic_nodes.inputs();
-                    let [o] = logic_nodes.outputs();
-                    let a_value = node_map[*a].logic.value;
-                    let b_value = node_map[*b].logic.value;
-
-                    Some((
-                        *o,
-                        match (a_value, b_value) {
-                            (Value::H, Value::H) => Value::L,
-                            (Value::H, Value::L) => Value::H,
-                            (Value::L, Value::H) => Value::H,
-                            (Value::L, Value::L) => Value::H,
-                            (Value::Z, _) | (_, Value::Z) | (_, Value::X) | (Value::X, _) => Value::X,
-                        },
-                    ))
-                }
-                Gate::Const { logic: ConstLogic { nodes: _, name: _ }, location: _, direction: _ } => None, // const nodes do not need to update becuase they always output the value they were created with
-                Gate::Unerror { logic: UnerrorLogic { nodes: logic_nodes, _dont_construct: () }, location: _, direction: _ } => {
-                    let [in_] = logic_nodes.inputs();
-                    let [out] = logic_nodes.outputs();
-                    let in_value = node_map[*in_].logic.value;
-                    Some((*out, if let Value::X = in_value { Value::L } else { in_value }))
-                }
-                Gate::Custom(_) => None, // custom gates do not have to compute values because their nodes are connected to their inputs or are passthrough nodes and should automatically have the right values
-                Gate::Button { logic: _, location: _, direction: _ } => None, // buttons do not need to update their values
-                Gate::TristateBuffer { logic: TristateBufferLogic { nodes, _dont_construct }, location: _, direction: _ } => {
-                    let [data, enable] = nodes.inputs();
-                    let [output] = nodes.outputs();
-                    let enable_value = node_map[*enable].logic.value;
-                    let data_value = node_map[*data].logic.value;
-
-                    Some((*output, match enable_value {
-                        Value::H => data_value,
-                        Value::L => Value::Z,
-                        Value::Z => Value::X,
-                        Value::X => Value::X,
-                    }))
-                }
-            }
-        })
-        .collect();
+    // only the gates whose inputs changed in the previous subtick are evaluated because every other gate would just produce the same thing it did last time
+    // gates and nodes that were removed after they were scheduled are skipped
+    let gate_outputs: Vec<(NodeKey, Value)> = scheduler.gate_queue.drain().filter_map(|gate| gates.get(gate).and_then(|gate| evaluate_gate(node_map, gate))).collect();
 
     for (node, value) in gate_outputs {
-        set_node_production(node_map, node, value);
-    }
-
-    // TODO: find a more efficient way to do this (disjoint union set data structure?)
-    let mut node_values: BTreeMap<_, _> = node_map.iter().map(|(nk, _)| (nk, Value::Z)).collect();
-    for (cur_node, node) in &*node_map {
-        if let Some(production) = node.logic.production {
-            let mut already: HashSet<_> = HashSet::new();
-            let mut queue: Vec<_> = node.connections.adjacent().iter().copied().chain(std::iter::once(cur_node)).collect();
-            while let Some(adj) = queue.pop() {
-                if already.contains(&adj) {
-                    continue;
-                }
-                already.insert(adj);
+        set_node_production(node_map, scheduler, node, value);
+    }
 
-                let result = Value::join(node_values[&adj], production);
-                *node_values.get_mut(&adj).unwrap() = result;
+    // every net whose productions changed takes on the combination of the productions of all of the nodes in it
+    let mut resolved = HashSet::new();
+    for node in std::mem::take(&mut scheduler.net_queue) {
+        if !node_map.contains_key(node) {
+            continue;
+        }
+        let net = connections::find_net(node_map, node);
+        if !resolved.insert(net) {
+            continue;
+        }
 
-                queue.extend(node_map[adj].connections.adjacent());
+        let members = connections::net_members(node_map, net);
+        let value = members.iter().filter_map(|member| node_map[*member].logic.production).fold(Value::Z, Value::join);
+        for member in members {
+            if node_map[member].logic.value != value {
+                node_map[member].logic.value = value;
+                if let Some(gate) = fan_out(node_map, member) {
+                    scheduler.gate_queue.insert(gate);
+                }
             }
         }
     }
-    for (node, value) in node_values {
-        node_map[node].logic.value = value;
+}
+
+fn evaluate_gate(node_map: &NodeMap, gate: &Gate) -> Option<(NodeKey, Value)> {
+    match gate {
+        Gate::Nand { logic: NandLogic { nodes: logic_nodes, _dont_construct: () }, location: _, direction: _ } => {
+            let [a, b] = logic_nodes.inputs();
+            let [o] = logic_nodes.outputs();
+            let a_value = node_map[*a].logic.value;
+            let b_value = node_map[*b].logic.value;
+
+            Some((
+                *o,
+                match (a_value, b_value) {
+                    (Value::H, Value::H) => Value::L,
+                    (Value::H, Value::L) => Value::H,
+                    (Value::L, Value::H) => Value::H,
+                    (Value::L, Value::L) => Value::H,
+                    (Value::Z, _) | (_, Value::Z) | (_, Value::X) | (Value::X, _) => Value::X,
+                },
+            ))
+        }
+        Gate::Const { logic: ConstLogic { nodes: _, name: _ }, location: _, direction: _ } => None, // const nodes do not need to update becuase they always output the value they were created with
+        Gate::Unerror { logic: UnerrorLogic { nodes: logic_nodes, _dont_construct: () }, location: _, direction: _ } => {
+            let [in_] = logic_nodes.inputs();
+            let [out] = logic_nodes.outputs();
+            let in_value = node_map[*in_].logic.value;
+            Some((*out, if let Value::X = in_value { Value::L } else { in_value }))
+        }
+        Gate::Custom(_) => None, // custom gates do not have to compute values because their nodes are connected to their inputs or are passthrough nodes and should automatically have the right values
+        Gate::Button { logic: _, location: _, direction: _ } => None, // buttons do not need to update their values
+        Gate::TristateBuffer { logic: TristateBufferLogic { nodes, _dont_construct }, location: _, direction: _ } => {
+            let [data, enable] = nodes.inputs();
+            let [output] = nodes.outputs();
+            let enable_value = node_map[*enable].logic.value;
+            let data_value = node_map[*data].logic.value;
+
+            Some((*output, match enable_value {
+                Value::H => data_value,
+                Value::L => Value::Z,
+                Value::Z => Value::X,
+                Value::X => Value::X,
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        import,
+        simulation::{
+            edit::NewGate,
+            tests::{assert_same_behaviour, PROJECT},
+            Gate, GateDirection, GateKey, Simulation,
+        },
+    };
+
+    // full evaluates every gate and net on every update like before there was a scheduler, and scheduled only evaluates what the scheduler says changed
+    #[test]
+    fn scheduled_matches_full_evaluation_on_project() {
+        let mut scheduled = import::import(PROJECT).unwrap();
+        let mut full = import::import(PROJECT).unwrap();
+        assert_same_behaviour(&mut scheduled, &mut full, 300, Some(12345), |_, _, full| full.scheduler.schedule_everything());
+    }
+
+    #[test]
+    fn scheduled_matches_full_evaluation_on_oscillator_and_bus() {
+        // a nand that reads its own output (through an unerror so that it starts out as L instead of staying X), which changes every other update,
+        // driving a bus through two tristate buffers that are turned on and off by buttons
+        fn build() -> (Simulation, [GateKey; 2]) {
+            let mut simulation = Simulation::new();
+            let oscillator = simulation.add_gate(None, NewGate::Nand, (0.0, 0.0), GateDirection::LTR);
+            let feedback = simulation.add_gate(None, NewGate::Unerror, (0.0, 0.0), GateDirection::LTR);
+            let [oscillator_out] = *Gate::outputs(&simulation.circuits, &simulation.gates, oscillator) else { unreachable!() };
+            let [feedback_in] = *Gate::inputs(&simulation.circuits, &simulation.gates, feedback) else { unreachable!() };
+            let [feedback_out] = *Gate::outputs(&simulation.circuits, &simulation.gates, feedback) else { unreachable!() };
+            simulation.connect(feedback_in, oscillator_out);
+            for input in Gate::inputs(&simulation.circuits, &simulation.gates, oscillator).to_vec() {
+                simulation.connect(input, feedback_out);
+            }
+            let inverter = simulation.add_gate(None, NewGate::Nand, (0.0, 0.0), GateDirection::LTR);
+            let [inverter_out] = *Gate::outputs(&simulation.circuits, &simulation.gates, inverter) else { unreachable!() };
+            for input in Gate::inputs(&simulation.circuits, &simulation.gates, inverter).to_vec() {
+                simulation.connect(input, oscillator_out);
+            }
+
+            let mut enables = Vec::new();
+            let mut bus = None;
+            for data in [oscillator_out, inverter_out] {
+                let buffer = simulation.add_gate(None, NewGate::TristateBuffer, (0.0, 0.0), GateDirection::LTR);
+                let button = simulation.add_gate(None, NewGate::Button, (0.0, 0.0), GateDirection::LTR);
+                let [buffer_data, buffer_enable] = *Gate::inputs(&simulation.circuits, &simulation.gates, buffer) else { unreachable!() };
+                let [buffer_out] = *Gate::outputs(&simulation.circuits, &simulation.gates, buffer) else { unreachable!() };
+                let [button_out] = *Gate::outputs(&simulation.circuits, &simulation.gates, button) else { unreachable!() };
+                simulation.connect(buffer_data, data);
+                simulation.connect(buffer_enable, button_out);
+                match bus {
+                    Some(bus) => simulation.connect(bus, buffer_out),
+                    None => bus = Some(buffer_out),
+                }
+                enables.push(button);
+            }
+            let unerror = simulation.add_gate(None, NewGate::Unerror, (0.0, 0.0), GateDirection::LTR);
+            let [unerror_in] = *Gate::inputs(&simulation.circuits, &simulation.gates, unerror) else { unreachable!() };
+            simulation.connect(unerror_in, bus.unwrap());
+
+            (simulation, [enables[0], enables[1]])
+        }
+
+        let (mut scheduled, enables) = build();
+        let (mut full, _) = build();
+        // nothing on the bus, then each buffer alone, then both fighting over it
+        assert_same_behaviour(&mut scheduled, &mut full, 40, None, |tick, scheduled, full| {
+            for simulation in [&mut *scheduled, &mut *full] {
+                match tick {
+                    10 => simulation.set_button(enables[0], true),
+                    20 => {
+                        simulation.set_button(enables[0], false);
+                        simulation.set_button(enables[1], true);
+                    }
+                    30 => simulation.set_button(enables[0], true),
+                    _ => {}
+                }
+            }
+            full.scheduler.schedule_everything();
+        });
     }
 }
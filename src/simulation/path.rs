@@ -0,0 +1,136 @@
+// human readable names for gates and nodes that stay the same between runs of the program, unlike keys
+//
+// a gate is named by the names of the gates it is nested in and its own name, each together with the index of that gate in its parent:
+//     main/4 bit adder[2]/full adder[1]
+// and a node is named by the gate it belongs to and whether it is an input or an output together with its index:
+//     main/4 bit adder[2]/full adder[1]/out[0]
+// for the nodes of a subcircuit, "in" and "out" refer to the inputs and outputs of the subcircuit itself
+// names come from project files and can have anything in them, so '/', '[', ']' and '%' in names are written as % and their hex code like in urls:
+//     main/a%2Fb%5B1%5D[0] is the first gate in main, which is named a/b[1]
+
+use crate::simulation::{hierarchy, CircuitKey, Gate, GateKey, NodeKey, Simulation};
+
+// the name of the root of every path, which stands for the toplevel gates
+pub const TOPLEVEL_NAME: &str = "main";
+
+pub fn gate_path(simulation: &Simulation, gate: GateKey) -> Option<String> {
+    let mut segments = vec![TOPLEVEL_NAME.to_string()];
+    if find_gate_segments(simulation, &simulation.toplevel_gates, &|gk| gk == gate, &mut segments) {
+        Some(segments.join("/"))
+    } else {
+        None
+    }
+}
+
+pub fn node_path(simulation: &Simulation, node: NodeKey) -> Option<String> {
+    let (gate_path, which, index) = match simulation.nodes[node].parent.kind() {
+        hierarchy::NodeParentKind::GateIn(gk, i) => (gate_path(simulation, gk)?, "in", i),
+        hierarchy::NodeParentKind::GateOut(gk, i) => (gate_path(simulation, gk)?, "out", i),
+        hierarchy::NodeParentKind::CircuitIn(ck, i) => (circuit_path(simulation, ck)?, "in", i),
+        hierarchy::NodeParentKind::CircuitOut(ck, i) => (circuit_path(simulation, ck)?, "out", i),
+    };
+    Some(format!("{gate_path}/{which}[{index}]"))
+}
+
+pub fn find_gate(simulation: &Simulation, path: &str) -> Option<GateKey> {
+    let mut segments = path.split('/');
+    if segments.next()? != TOPLEVEL_NAME {
+        return None;
+    }
+
+    let mut children = &simulation.toplevel_gates;
+    let mut gate = None;
+    for segment in segments {
+        // the gate found by the previous segment has to be a subcircuit for there to be anything inside of it
+        if let Some(gate) = gate {
+            let Gate::Custom(ck) = simulation.gates[gate] else { return None };
+            children = &simulation.circuits[ck].gates;
+        }
+
+        let (name, index) = parse_segment(segment)?;
+        let child = children.get(index)?;
+        if simulation.gates[child].name(&simulation.circuits) != name {
+            return None;
+        }
+        gate = Some(child);
+    }
+
+    gate
+}
+
+pub fn find_node(simulation: &Simulation, path: &str) -> Option<NodeKey> {
+    let (gate_path, node_segment) = path.rsplit_once('/')?;
+    let gate = find_gate(simulation, gate_path)?;
+    let (which, index) = parse_segment(node_segment)?;
+    match which.as_str() {
+        "in" => Gate::inputs(&simulation.circuits, &simulation.gates, gate).get(index).copied(),
+        "out" => Gate::outputs(&simulation.circuits, &simulation.gates, gate).get(index).copied(),
+        _ => None,
+    }
+}
+
+// the path of the gate that is an instance of a circuit
+fn circuit_path(simulation: &Simulation, circuit: CircuitKey) -> Option<String> {
+    let mut segments = vec![TOPLEVEL_NAME.to_string()];
+    if find_gate_segments(simulation, &simulation.toplevel_gates, &|gk| matches!(simulation.gates[gk], Gate::Custom(ck) if ck == circuit), &mut segments) {
+        Some(segments.join("/"))
+    } else {
+        None
+    }
+}
+
+fn find_gate_segments(simulation: &Simulation, children: &hierarchy::GateChildren, is_target: &dyn Fn(GateKey) -> bool, segments: &mut Vec<String>) -> bool {
+    for (index, &child) in children.iter().enumerate() {
+        segments.push(format_segment(simulation.gates[child].name(&simulation.circuits), index));
+        if is_target(child) {
+            return true;
+        }
+        if let Gate::Custom(ck) = simulation.gates[child] {
+            if find_gate_segments(simulation, &simulation.circuits[ck].gates, is_target, segments) {
+                return true;
+            }
+        }
+        segments.pop();
+    }
+
+    false
+}
+
+pub(crate) fn format_segment(name: &str, index: usize) -> String {
+    format!("{}[{index}]", name.replace('%', "%25").replace('/', "%2F").replace('[', "%5B").replace(']', "%5D"))
+}
+
+// the name that it gives back is the name of the gate and not the way that it is written in the path
+pub(crate) fn parse_segment(segment: &str) -> Option<(String, usize)> {
+    let (name, index) = segment.strip_suffix(']')?.rsplit_once('[')?;
+    // %25 goes last so that the % that it gives back does not start another code
+    let name = name.replace("%2F", "/").replace("%5B", "[").replace("%5D", "]").replace("%25", "%");
+    Some((name, index.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        import,
+        simulation::{tests::PROJECT, Gate},
+    };
+
+    #[test]
+    fn names_with_path_characters_round_trip() {
+        let mut simulation = import::import(PROJECT).unwrap();
+        // adder4 has subcircuits inside of it, so the paths of everything inside of those go through this name too
+        let adder4 = simulation.find_gate("main/adder4[8]").unwrap();
+        let Gate::Custom(ck) = simulation.gates[adder4] else { panic!("adder4 is not a subcircuit") };
+        simulation.circuits[ck].name = "a/b[1]%2F".to_string();
+        assert_eq!(simulation.gate_path(adder4).as_deref(), Some("main/a%2Fb%5B1%5D%252F[8]"));
+
+        for (gate, _) in simulation.gates.iter() {
+            let path = simulation.gate_path(gate).unwrap();
+            assert!(simulation.find_gate(&path) == Some(gate), "{path} does not find its gate");
+        }
+        for (node, _) in simulation.nodes.iter() {
+            let path = simulation.node_path(node).unwrap();
+            assert!(simulation.find_node(&path) == Some(node), "{path} does not find its node");
+        }
+    }
+}
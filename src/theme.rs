@@ -13,13 +13,25 @@ pub(crate) struct Theme {
     pub(crate) button_hover_fg: Color,
     pub(crate) button_pressed_bg: Color,
     pub(crate) button_pressed_fg: Color,
+    pub(crate) button_font_size: u32,
 
     pub(crate) simulation_bg_color: Color,
+    pub(crate) grid_spacing: f32, // gates snap to multiples of this
+    pub(crate) grid_major_every: u32,
+    pub(crate) grid_color: Color,
+    pub(crate) grid_major_color: Color,
+    pub(crate) grid_min_line_spacing: f32, // lines that would be closer together than this on screen are not drawn
+    pub(crate) alignment_guide_color: Color,
+    pub(crate) alignment_guide_dist: f32, // how close on screen two gates have to be to lining up for a guide to show
 
     pub(crate) gate_color: Color,
     pub(crate) gate_hover_color: Color,
     pub(crate) gate_text_color: Color,
     pub(crate) gate_hover_dist: f32,
+    pub(crate) gate_selected_color: Color,
+    pub(crate) gate_selected_width: f32,
+    pub(crate) box_select_color: Color,
+    pub(crate) box_select_outline_color: Color,
 
     pub(crate) on_color: Color,
     pub(crate) off_color: Color,
@@ -28,9 +40,53 @@ pub(crate) struct Theme {
     pub(crate) node_hover_color: Color,
     pub(crate) node_rad: f32,
     pub(crate) node_hover_dist: f32,
+    pub(crate) node_label_color: Color,
+    pub(crate) node_label_font_size: u32,
+    pub(crate) node_label_dist: f32,
+    pub(crate) bundle_outline_color: Color,
+    pub(crate) net_dim_color: Color, // drawn over everything except the net being hovered over
+    pub(crate) net_info_height: f32, // the label in the bottom corner that says how many gates drive the net being hovered over
+    pub(crate) net_info_bg_color: Color,
+    pub(crate) net_info_text_color: Color,
+    pub(crate) net_info_conflict_color: Color, // the text color when more than one gate drives the net
+    pub(crate) net_info_font_size: u32,
+    pub(crate) net_info_padding: f32,
 
     pub(crate) connection_width: f32,
     pub(crate) connection_hover_dist: f32,
+
+    pub(crate) camera_zoom_speed: f32, // how much one notch of the mouse wheel zooms by
+    pub(crate) camera_min_zoom: f32,
+    pub(crate) camera_max_zoom: f32,
+    pub(crate) camera_fit_padding: f32,
+    pub(crate) fit_button_size: (f32, f32), // the button in the corner of the simulation view that fits the camera to the gates
+
+    pub(crate) breadcrumb_bar_height: f32,
+    pub(crate) breadcrumb_bg_color: Color,
+    pub(crate) breadcrumb_hover_color: Color,
+    pub(crate) breadcrumb_text_color: Color,
+    pub(crate) breadcrumb_font_size: u32,
+    pub(crate) breadcrumb_padding: f32,
+    pub(crate) breadcrumb_separator_width: f32,
+
+    pub(crate) waveform_bg_color: Color,
+    pub(crate) waveform_hover_color: Color,
+    pub(crate) waveform_text_color: Color,
+    pub(crate) waveform_transition_color: Color,
+    pub(crate) waveform_font_size: u32,
+    pub(crate) waveform_padding: f32,
+    pub(crate) waveform_row_height: f32,
+    pub(crate) waveform_label_width: f32,
+    pub(crate) waveform_sample_width: f32,
+    pub(crate) waveform_line_width: f32,
+
+    pub(crate) palette_width: f32,
+    pub(crate) palette_row_height: f32,
+    pub(crate) palette_bg_color: Color,
+    pub(crate) palette_hover_color: Color,
+    pub(crate) palette_text_color: Color,
+    pub(crate) palette_font_size: u32,
+    pub(crate) palette_padding: f32,
 }
 
 impl Theme {
@@ -47,13 +103,26 @@ impl Theme {
         button_hover_fg: Color::rgb(0, 0, 0),
         button_pressed_bg: Color::rgb(100, 100, 100),
         button_pressed_fg: Color::rgb(0, 0, 0),
+        button_font_size: 10,
 
         simulation_bg_color: Color::rgb(180, 180, 180),
+        // the python layout helpers space nodes 20 apart and make gates 50 wide, and this divides both
+        grid_spacing: 10.0,
+        grid_major_every: 5,
+        grid_color: Color::rgba(0, 0, 0, 15),
+        grid_major_color: Color::rgba(0, 0, 0, 35),
+        grid_min_line_spacing: 6.0,
+        alignment_guide_color: Color::rgb(0, 150, 255),
+        alignment_guide_dist: 3.0,
 
         gate_color: Color::rgb(100, 100, 100),
         gate_hover_color: Color::rgba(255, 255, 255, 50),
         gate_text_color: Color::rgb(255, 255, 255),
         gate_hover_dist: 5.0,
+        gate_selected_color: Color::rgb(255, 200, 0),
+        gate_selected_width: 2.0,
+        box_select_color: Color::rgba(255, 200, 0, 30),
+        box_select_outline_color: Color::rgb(255, 200, 0),
         on_color: Color::rgb(0, 255, 0),
         off_color: Color::rgb(50, 50, 50),
         high_impedance_color: Color::rgb(0, 0, 255),
@@ -61,8 +130,52 @@ impl Theme {
         node_hover_color: Color::rgba(255, 255, 255, 50),
         node_rad: 5.0,
         node_hover_dist: 4.0,
+        node_label_color: Color::rgb(220, 220, 220),
+        node_label_font_size: 8,
+        node_label_dist: 4.0,
+        bundle_outline_color: Color::rgb(60, 60, 60),
+        net_dim_color: Color::rgba(180, 180, 180, 170),
+        net_info_height: 20.0,
+        net_info_bg_color: Color::rgba(0, 0, 0, 100),
+        net_info_text_color: Color::rgb(255, 255, 255),
+        net_info_conflict_color: Color::rgb(255, 0, 0),
+        net_info_font_size: 10,
+        net_info_padding: 6.0,
         connection_width: 2.5,
         connection_hover_dist: 4.0,
+
+        camera_zoom_speed: 1.1,
+        camera_min_zoom: 0.05,
+        camera_max_zoom: 10.0,
+        camera_fit_padding: 20.0,
+        fit_button_size: (30.0, 20.0),
+
+        breadcrumb_bar_height: 20.0,
+        breadcrumb_bg_color: Color::rgba(0, 0, 0, 100),
+        breadcrumb_hover_color: Color::rgba(255, 255, 255, 50),
+        breadcrumb_text_color: Color::rgb(255, 255, 255),
+        breadcrumb_font_size: 10,
+        breadcrumb_padding: 6.0,
+        breadcrumb_separator_width: 12.0,
+
+        waveform_bg_color: Color::rgb(20, 20, 20),
+        waveform_hover_color: Color::rgba(255, 255, 255, 20),
+        waveform_text_color: Color::rgb(220, 220, 220),
+        waveform_transition_color: Color::rgb(120, 120, 120),
+        waveform_font_size: 10,
+        waveform_padding: 4.0,
+        waveform_row_height: 24.0,
+        waveform_label_width: 120.0,
+        waveform_sample_width: 4.0,
+        waveform_line_width: 1.5,
+
+        palette_width: 150.0,
+        palette_row_height: 20.0,
+        palette_bg_color: Color::rgb(40, 40, 40),
+        palette_hover_color: Color::rgb(70, 70, 70),
+        palette_text_color: Color::rgb(220, 220, 220),
+        palette_font_size: 10,
+        palette_padding: 6.0,
     };
 
     fn linear_ease(x: f32) -> f32 {
@@ -3,16 +3,34 @@ pub(crate) mod widgets;
 
 pub(crate) struct UI {
     pub(crate) new_slide_over: widgets::slide_over::SlideOverState,
-    pub(crate) btree: widgets::btree::BTree<widgets::simulation::SimulationWidgetState>,
+    pub(crate) btree: widgets::btree::BTree<Pane>,
     pub(crate) tps_slider_state: widgets::slider::SliderState<isize>,
+    pub(crate) save_button_state: widgets::button::ButtonState,
+    // shared by every waveform pane
+    pub(crate) waveform: widgets::waveform::WaveformState,
+    // shared by the palette and every simulation pane, which is where gates are dragged to
+    pub(crate) palette: widgets::palette::PaletteState,
+}
+
+// what is shown in each leaf of the btree
+#[derive(Clone)]
+pub(crate) enum Pane {
+    Simulation(widgets::simulation::SimulationWidgetState),
+    Waveform,
 }
 
 impl UI {
     pub(crate) fn new() -> UI {
         UI {
             new_slide_over: widgets::slide_over::SlideOverState::new(),
-            btree: widgets::btree::BTree::new_single(widgets::simulation::SimulationWidgetState::new()),
+            btree: widgets::btree::BTree::VSplit {
+                top: Box::new(widgets::btree::BTree::new_single(Pane::Simulation(widgets::simulation::SimulationWidgetState::new()))),
+                bottom: Box::new(widgets::btree::BTree::new_single(Pane::Waveform)),
+            },
             tps_slider_state: widgets::slider::SliderState::new(),
+            save_button_state: widgets::button::ButtonState::new(),
+            waveform: widgets::waveform::WaveformState::new(),
+            palette: widgets::palette::PaletteState::new(),
         }
     }
 }
@@ -1,9 +1,12 @@
 pub(crate) mod btree;
 pub(crate) mod button;
+pub(crate) mod either;
 #[macro_use]
 pub(crate) mod flow;
+pub(crate) mod palette;
 pub(crate) mod simulation;
 pub(crate) mod slide_over;
 pub(crate) mod slider;
 pub(crate) mod submodule;
 pub(crate) mod test_rect;
+pub(crate) mod waveform;
@@ -6,7 +6,7 @@ use crate::{
     ui::widgets::button::ButtonState,
     view::{
         id::{ViewId, ViewIdMaker},
-        lens::{self, Lens},
+        lens::Lens,
         GeneralEvent, SizeConstraints, TargetedEvent, View, ViewWithoutLayout,
     },
     App,
@@ -30,12 +30,12 @@ impl<Child> BTree<Child> {
 enum BTreeView<Data, ChildView: ViewWithoutLayout<Data>, SplitHButtonView: ViewWithoutLayout<Data>> {
     Single { splith_button: SplitHButtonView, child_view: ChildView, _phantom: PhantomData<fn(&Data)> },
     HSplit { left: Box<BTreeView<Data, ChildView, SplitHButtonView>>, right: Box<BTreeView<Data, ChildView, SplitHButtonView>> },
-    VSplit {},
+    VSplit { top: Box<BTreeView<Data, ChildView, SplitHButtonView>>, bottom: Box<BTreeView<Data, ChildView, SplitHButtonView>> },
 }
 enum BTreeLayout<'original, Data, ChildView: ViewWithoutLayout<Data> + 'original, SplitHButtonView: ViewWithoutLayout<Data> + 'original> {
     Single { splith_button_offset: Vector2f, splith_button: SplitHButtonView::WithLayout<'original>, child_view: ChildView::WithLayout<'original> },
     HSplit { left: Box<BTreeLayout<'original, Data, ChildView, SplitHButtonView>>, right: Box<BTreeLayout<'original, Data, ChildView, SplitHButtonView>> },
-    VSplit {},
+    VSplit { top: Box<BTreeLayout<'original, Data, ChildView, SplitHButtonView>>, bottom: Box<BTreeLayout<'original, Data, ChildView, SplitHButtonView>> },
 }
 
 impl<Data, ChildView: ViewWithoutLayout<Data>, SplitHButtonView: ViewWithoutLayout<Data>> ViewWithoutLayout<Data> for BTreeView<Data, ChildView, SplitHButtonView> {
@@ -56,7 +56,12 @@ impl<Data, ChildView: ViewWithoutLayout<Data>, SplitHButtonView: ViewWithoutLayo
                 let right_layout = right.layout(half_width_sc);
                 BTreeLayout::HSplit { left: Box::new(left_layout), right: Box::new(right_layout) }
             }
-            BTreeView::VSplit {} => BTreeLayout::VSplit {},
+            BTreeView::VSplit { top, bottom } => {
+                let half_height_sc = SizeConstraints { min: Vector2f::new(0.0, 0.0), max: Vector2f::new(sc.max.x, sc.max.y / 2.0) };
+                let top_layout = top.layout(half_height_sc);
+                let bottom_layout = bottom.layout(half_height_sc);
+                BTreeLayout::VSplit { top: Box::new(top_layout), bottom: Box::new(bottom_layout) }
+            }
         }
     }
 }
@@ -68,8 +73,15 @@ impl<Data, ChildView: ViewWithoutLayout<Data>, SplitHButtonView: ViewWithoutLayo
                 child_view.draw(app, target, top_left, hover);
                 // splith_button.draw(app, target, top_left + *splith_button_offset, hover); TODO: figure this out
             }
-            BTreeLayout::HSplit { left, right } => todo!(),
-            BTreeLayout::VSplit {} => todo!(),
+            // the children are btrees too, so they can draw right onto the same target without going through another texture
+            BTreeLayout::HSplit { left, right } => {
+                left.draw_inner(app, target, top_left, hover);
+                right.draw_inner(app, target, top_left + Vector2f::new(left.size().x, 0.0), hover);
+            }
+            BTreeLayout::VSplit { top, bottom } => {
+                top.draw_inner(app, target, top_left, hover);
+                bottom.draw_inner(app, target, top_left + Vector2f::new(0.0, top.size().y), hover);
+            }
         }
     }
 
@@ -79,16 +91,17 @@ impl<Data, ChildView: ViewWithoutLayout<Data>, SplitHButtonView: ViewWithoutLayo
                 // splith_button.find_hover(top_left + *splith_button_offset, mouse).or(child_view.find_hover(top_left, mouse)) TODO: figure this out
                 child_view.find_hover(top_left, mouse)
             }
-            BTreeLayout::HSplit { left, right } => todo!(),
-            BTreeLayout::VSplit {} => todo!(),
+            // both sides are always asked (not or_else) because some views only find out where they are on the screen in find_hover, and they have to know even when the mouse is over the other side
+            BTreeLayout::HSplit { left, right } => left.find_hover(top_left, mouse).or(right.find_hover(top_left + Vector2f::new(left.size().x, 0.0), mouse)),
+            BTreeLayout::VSplit { top, bottom } => top.find_hover(top_left, mouse).or(bottom.find_hover(top_left + Vector2f::new(0.0, top.size().y), mouse)),
         }
     }
 
     fn size(&self) -> Vector2f {
         match self {
             BTreeLayout::Single { splith_button_offset: _, splith_button: _, child_view } => child_view.size(),
-            BTreeLayout::HSplit { left, right } => todo!(),
-            BTreeLayout::VSplit {} => todo!(),
+            BTreeLayout::HSplit { left, right } => Vector2f::new(left.size().x + right.size().x, left.size().y.max(right.size().y)),
+            BTreeLayout::VSplit { top, bottom } => Vector2f::new(top.size().x.max(bottom.size().x), top.size().y + bottom.size().y),
         }
     }
 
@@ -98,8 +111,14 @@ impl<Data, ChildView: ViewWithoutLayout<Data>, SplitHButtonView: ViewWithoutLayo
                 splith_button.send_targeted_event(app, data, target, event);
                 child_view.send_targeted_event(app, data, target, event);
             }
-            BTreeLayout::HSplit { left, right } => todo!(),
-            BTreeLayout::VSplit {} => todo!(),
+            BTreeLayout::HSplit { left, right } => {
+                left.send_targeted_event(app, data, target, event);
+                right.send_targeted_event(app, data, target, event);
+            }
+            BTreeLayout::VSplit { top, bottom } => {
+                top.send_targeted_event(app, data, target, event);
+                bottom.send_targeted_event(app, data, target, event);
+            }
         }
     }
 
@@ -110,69 +129,145 @@ impl<Data, ChildView: ViewWithoutLayout<Data>, SplitHButtonView: ViewWithoutLayo
                 splith_button.general_event(app, data, event);
                 child_view.general_event(app, data, event);
             }
-            BTreeLayout::HSplit { left, right } => todo!(),
-            BTreeLayout::VSplit {} => todo!(),
+            BTreeLayout::HSplit { left, right } => {
+                left.general_event(app, data, event);
+                right.general_event(app, data, event);
+            }
+            BTreeLayout::VSplit { top, bottom } => {
+                top.general_event(app, data, event);
+                bottom.general_event(app, data, event);
+            }
         }
     }
 }
 
-pub(crate) struct BTreeChildLens<Data, Child, BTreeLens: Lens<Data, BTree<Child>>> {
+// which child to go into at each level to get to a subtree, as bits so that the lenses that use it can stay Copy (0 for left or top, 1 for right or bottom)
+#[derive(Copy, Clone)]
+pub(crate) struct BTreePath {
+    bits: u64,
+    depth: u32,
+}
+impl BTreePath {
+    const ROOT: BTreePath = BTreePath { bits: 0, depth: 0 };
+
+    fn first(self) -> BTreePath {
+        assert!(self.depth < u64::BITS, "btree too deep");
+        BTreePath { bits: self.bits, depth: self.depth + 1 }
+    }
+    fn second(self) -> BTreePath {
+        assert!(self.depth < u64::BITS, "btree too deep");
+        BTreePath { bits: self.bits | (1 << self.depth), depth: self.depth + 1 }
+    }
+    fn goes_second(self, level: u32) -> bool {
+        self.bits & (1 << level) != 0
+    }
+}
+
+fn subtree<Child>(btree: &BTree<Child>, path: BTreePath) -> &BTree<Child> {
+    (0..path.depth).fold(btree, |btree, level| match btree {
+        BTree::Single { child: _, splith_button: _ } => panic!("btree path goes further than btree"),
+        BTree::HSplit { left, right } => if path.goes_second(level) { right } else { left },
+        BTree::VSplit { top, bottom } => if path.goes_second(level) { bottom } else { top },
+    })
+}
+fn subtree_mut<Child>(btree: &mut BTree<Child>, path: BTreePath) -> &mut BTree<Child> {
+    (0..path.depth).fold(btree, |btree, level| match btree {
+        BTree::Single { child: _, splith_button: _ } => panic!("btree path goes further than btree"),
+        BTree::HSplit { left, right } => if path.goes_second(level) { right } else { left },
+        BTree::VSplit { top, bottom } => if path.goes_second(level) { bottom } else { top },
+    })
+}
+
+// lens to some part of a single at a path in a btree
+pub(crate) struct BTreeSingleLens<Data, Child, Part, BTreeLens: Lens<Data, BTree<Child>>> {
     btree_lens: BTreeLens,
-    get_child: fn(&BTree<Child>) -> &Child,
-    get_child_mut: fn(&mut BTree<Child>) -> &mut Child,
-    _phantom: PhantomData<fn(&Data) -> &Child>,
+    path: BTreePath,
+    get_part: fn(&BTree<Child>) -> &Part,
+    get_part_mut: fn(&mut BTree<Child>) -> &mut Part,
+    _phantom: PhantomData<fn(&Data) -> &Part>,
+}
+pub(crate) type BTreeChildLens<Data, Child, BTreeLens> = BTreeSingleLens<Data, Child, Child, BTreeLens>;
+impl<Data, Child, Part, BTreeLens: Lens<Data, BTree<Child>> + Clone> Clone for BTreeSingleLens<Data, Child, Part, BTreeLens> {
+    fn clone(&self) -> BTreeSingleLens<Data, Child, Part, BTreeLens> {
+        BTreeSingleLens { btree_lens: self.btree_lens.clone(), path: self.path, get_part: self.get_part, get_part_mut: self.get_part_mut, _phantom: PhantomData }
+    }
 }
-impl<Data, Child, BTreeLens: Lens<Data, BTree<Child>> + Clone> Clone for BTreeChildLens<Data, Child, BTreeLens> {
-    fn clone(&self) -> BTreeChildLens<Data, Child, BTreeLens> {
-        BTreeChildLens { btree_lens: self.btree_lens.clone(), get_child: self.get_child, get_child_mut: self.get_child_mut, _phantom: PhantomData }
+impl<Data, Child, Part, BTreeLens: Lens<Data, BTree<Child>> + Copy> Copy for BTreeSingleLens<Data, Child, Part, BTreeLens> {}
+impl<Data, Child, Part, BTreeLens: Lens<Data, BTree<Child>>> BTreeSingleLens<Data, Child, Part, BTreeLens> {
+    // the same for every view of the single at this path, so it can be used for a view that keeps focus while other panes are split or change what they show
+    pub(crate) fn single_id(&self) -> ViewId {
+        ViewId::keyed(((self.path.depth as u128) << u64::BITS) | self.path.bits as u128)
     }
 }
-impl<Data, Child, BTreeLens: Lens<Data, BTree<Child>> + Copy> Copy for BTreeChildLens<Data, Child, BTreeLens> {}
-impl<Data, Child, BTreeLens: Lens<Data, BTree<Child>>> Lens<Data, Child> for BTreeChildLens<Data, Child, BTreeLens> {
-    fn with<'a, R: 'a, F: FnOnce(&Child) -> R>(&self, a: &Data, f: F) -> R {
-        self.btree_lens.with(a, |btree| f((self.get_child)(btree)))
+impl<Data, Child, Part, BTreeLens: Lens<Data, BTree<Child>>> Lens<Data, Part> for BTreeSingleLens<Data, Child, Part, BTreeLens> {
+    fn with<'a, R: 'a, F: FnOnce(&Part) -> R>(&self, a: &Data, f: F) -> R {
+        self.btree_lens.with(a, |btree| f((self.get_part)(subtree(btree, self.path))))
     }
 
-    fn with_mut<'a, R: 'a, F: FnOnce(&mut Child) -> R>(&self, a: &mut Data, f: F) -> R {
-        self.btree_lens.with_mut(a, |btree| f((self.get_child_mut)(btree)))
+    fn with_mut<'a, R: 'a, F: FnOnce(&mut Part) -> R>(&self, a: &mut Data, f: F) -> R {
+        self.btree_lens.with_mut(a, |btree| f((self.get_part_mut)(subtree_mut(btree, self.path))))
     }
 }
 
 pub(crate) fn btree<Child: Clone, BTreeLens: Lens<Data, BTree<Child>> + Copy, ChildView: ViewWithoutLayout<Data>, Data>(
-    app: &App,
+    _app: &App,
     id_maker: &mut ViewIdMaker,
     data: &Data,
     btree_lens: BTreeLens,
     view_child: impl Fn(&mut ViewIdMaker, BTreeChildLens<Data, Child, BTreeLens>, &Data) -> ChildView + Copy,
 ) -> impl ViewWithoutLayout<Data> {
-    btree_lens.with(data, move |btree| match btree {
-        BTree::Single { child: _, splith_button: _ } => {
+    btree_at_path(id_maker, data, btree_lens, BTreePath::ROOT, view_child)
+}
+
+fn btree_at_path<Child: Clone, BTreeLens: Lens<Data, BTree<Child>> + Copy, ChildView: ViewWithoutLayout<Data>, Data>(
+    id_maker: &mut ViewIdMaker,
+    data: &Data,
+    btree_lens: BTreeLens,
+    path: BTreePath,
+    view_child: impl Fn(&mut ViewIdMaker, BTreeChildLens<Data, Child, BTreeLens>, &Data) -> ChildView + Copy,
+) -> BTreeView<Data, ChildView, impl ViewWithoutLayout<Data>> {
+    enum Kind {
+        Single,
+        HSplit,
+        VSplit,
+    }
+    let kind = btree_lens.with(data, |btree| match subtree(btree, path) {
+        BTree::Single { child: _, splith_button: _ } => Kind::Single,
+        BTree::HSplit { left: _, right: _ } => Kind::HSplit,
+        BTree::VSplit { top: _, bottom: _ } => Kind::VSplit,
+    });
+
+    match kind {
+        Kind::Single => {
+            fn get_splith_button<Child>(bt: &BTree<Child>) -> &ButtonState {
+                match bt {
+                    BTree::Single { child: _, splith_button } => splith_button,
+                    BTree::HSplit { left: _, right: _ } => panic!("btree single lens used with hsplit btree"),
+                    BTree::VSplit { top: _, bottom: _ } => panic!("btree single lens used with vsplit btree"),
+                }
+            }
+            fn get_splith_button_mut<Child>(bt: &mut BTree<Child>) -> &mut ButtonState {
+                match bt {
+                    BTree::Single { child: _, splith_button } => splith_button,
+                    BTree::HSplit { left: _, right: _ } => panic!("btree single lens used with hsplit btree"),
+                    BTree::VSplit { top: _, bottom: _ } => panic!("btree single lens used with vsplit btree"),
+                }
+            }
             let splith_button = crate::ui::widgets::button::button(
                 id_maker,
                 data,
-                lens::Compose::new(
-                    btree_lens,
-                    lens::Closures::new(
-                        |btree: &_| match btree {
-                            BTree::Single { child: _, splith_button } => splith_button,
-                            BTree::HSplit { left: _, right: _ } => panic!("btree single lens used with hsplit btree"),
-                            BTree::VSplit { top: _, bottom: _ } => panic!("btree single lens used with vsplit btree"),
-                        },
-                        |btree| match btree {
-                            BTree::Single { child: _, splith_button } => splith_button,
-                            BTree::HSplit { left: _, right: _ } => panic!("btree single lens used with hsplit btree"),
-                            BTree::VSplit { top: _, bottom: _ } => panic!("btree single lens used with vsplit btree"),
-                        },
-                    ),
-                ),
+                BTreeSingleLens { btree_lens, path, get_part: get_splith_button, get_part_mut: get_splith_button_mut, _phantom: PhantomData },
                 move |_, data| {
-                    btree_lens.with_mut(data, |btree| match btree {
-                        BTree::Single { child, splith_button: _ } => {
-                            *btree = BTree::HSplit { left: Box::new(BTree::new_single(child.clone())), right: Box::new(BTree::new_single(child.clone())) };
-                        }
+                    btree_lens.with_mut(data, |btree| {
+                        let btree = subtree_mut(btree, path);
+                        match btree {
+                            BTree::Single { child, splith_button: _ } => {
+                                *btree = BTree::HSplit { left: Box::new(BTree::new_single(child.clone())), right: Box::new(BTree::new_single(child.clone())) };
+                            }
 
-                        BTree::HSplit { left: _, right: _ } => panic!("splith button made for btree that is hsplit"),
-                        BTree::VSplit { top: _, bottom: _ } => panic!("splith button made for btree that is vsplit"),
+                            BTree::HSplit { left: _, right: _ } => panic!("splith button made for btree that is hsplit"),
+                            BTree::VSplit { top: _, bottom: _ } => panic!("splith button made for btree that is vsplit"),
+                        }
                     });
                 },
             );
@@ -191,19 +286,19 @@ pub(crate) fn btree<Child: Clone, BTreeLens: Lens<Data, BTree<Child>> + Copy, Ch
                     BTree::VSplit { top: _, bottom: _ } => panic!("get_child_mut called on vsplit btree"),
                 }
             }
-            BTreeView::Single { splith_button, child_view: view_child(id_maker, BTreeChildLens { btree_lens, _phantom: PhantomData, get_child, get_child_mut }, data), _phantom: PhantomData }
-        }
-        BTree::HSplit { left, right } => {
-            /* TODO
-            let left_lens = lens::Closures::new(todo!(), todo!());
-            let right_lens = lens::Closures::new(todo!(), todo!());
-            BTreeView::HSplit {
-                left: Box::new(self::btree(app, id_maker, data, lens::Compose::new(btree_lens, left_lens), view_child)),
-                right: Box::new(self::btree(app, id_maker, data, lens::Compose::new(btree_lens, right_lens), view_child)),
-            }
-            */
-            todo!()
+            BTreeView::Single {
+                splith_button,
+                child_view: view_child(id_maker, BTreeSingleLens { btree_lens, path, get_part: get_child, get_part_mut: get_child_mut, _phantom: PhantomData }, data),
+                _phantom: PhantomData,
+            }
         }
-        BTree::VSplit { top, bottom } => BTreeView::VSplit {},
-    })
+        Kind::HSplit => BTreeView::HSplit {
+            left: Box::new(btree_at_path(id_maker, data, btree_lens, path.first(), view_child)),
+            right: Box::new(btree_at_path(id_maker, data, btree_lens, path.second(), view_child)),
+        },
+        Kind::VSplit => BTreeView::VSplit {
+            top: Box::new(btree_at_path(id_maker, data, btree_lens, path.first(), view_child)),
+            bottom: Box::new(btree_at_path(id_maker, data, btree_lens, path.second(), view_child)),
+        },
+    }
 }
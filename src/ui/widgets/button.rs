@@ -1,9 +1,9 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, rc::Rc};
 
-use sfml::graphics::Shape;
+use sfml::graphics::{Shape, Transformable};
 
 use crate::{
-    graphics,
+    graphics::{self, CenterText, RectCenter},
     theme::Theme,
     view::{
         id::{ViewId, ViewIdMaker},
@@ -27,6 +27,7 @@ struct ButtonView<Data, GetButtonData: Lens<Data, ButtonState>, Callback: Fn(&cr
     pressed: bool,
     button_data_lens: GetButtonData,
     callback: Callback,
+    label: Option<(String, Rc<sfml::SfBox<graphics::Font>>)>,
 
     _phantom: PhantomData<fn(&Data) -> &ButtonState>,
 }
@@ -56,6 +57,20 @@ impl<Data, GetButtonData: Lens<Data, ButtonState>, Callback: Fn(&crate::App, &mu
         }
 
         target.draw(&rect_shape);
+
+        if let Some((label, font)) = &self.view.label {
+            let mut text = graphics::Text::new(label, font, Theme::DEFAULT.button_font_size);
+            text.set_fill_color(if self.view.pressed {
+                Theme::DEFAULT.button_pressed_fg
+            } else if hover == Some(self.view.id) {
+                Theme::DEFAULT.button_hover_fg
+            } else {
+                Theme::DEFAULT.button_normal_fg
+            });
+            text.center();
+            text.set_position(graphics::FloatRect::from_vecs(top_left, self.size).center());
+            target.draw(&text);
+        }
     }
 
     fn find_hover(&self, top_left: graphics::Vector2f, mouse: graphics::Vector2f) -> Option<ViewId> {
@@ -78,7 +93,7 @@ impl<Data, GetButtonData: Lens<Data, ButtonState>, Callback: Fn(&crate::App, &mu
     fn targeted_event(&self, _: &crate::App, data: &mut Data, event: TargetedEvent) {
         match event {
             TargetedEvent::LeftMouseDown(_) => self.view.button_data_lens.with_mut(data, |button_data| button_data.pressed = true),
-            TargetedEvent::RightMouseDown(_) => {}
+            TargetedEvent::RightMouseDown(_) | TargetedEvent::MouseWheelScrolled(_, _) | TargetedEvent::KeyPressed(_) | TargetedEvent::TextEntered(_) => {}
         }
     }
     fn general_event(&self, app: &crate::App, data: &mut Data, event: GeneralEvent) {
@@ -100,5 +115,22 @@ impl<Data, GetButtonData: Lens<Data, ButtonState>, Callback: Fn(&crate::App, &mu
 
 // TODO: should this return ButtonView instead of an opaque type?
 pub(crate) fn button<Data>(id_maker: &mut ViewIdMaker, data: &Data, get_button_data: impl Lens<Data, ButtonState>, callback: impl Fn(&crate::App, &mut Data)) -> impl ViewWithoutLayout<Data> {
-    ButtonView { id: id_maker.next_id(), pressed: get_button_data.with(data, |button_data| button_data.pressed), button_data_lens: get_button_data, callback, _phantom: PhantomData }
+    ButtonView { id: id_maker.next_id(), pressed: get_button_data.with(data, |button_data| button_data.pressed), button_data_lens: get_button_data, callback, label: None, _phantom: PhantomData }
+}
+pub(crate) fn labeled_button<Data>(
+    id_maker: &mut ViewIdMaker,
+    data: &Data,
+    get_button_data: impl Lens<Data, ButtonState>,
+    label: &str,
+    font: &Rc<sfml::SfBox<graphics::Font>>,
+    callback: impl Fn(&crate::App, &mut Data),
+) -> impl ViewWithoutLayout<Data> {
+    ButtonView {
+        id: id_maker.next_id(),
+        pressed: get_button_data.with(data, |button_data| button_data.pressed),
+        button_data_lens: get_button_data,
+        callback,
+        label: Some((label.to_string(), font.clone())),
+        _phantom: PhantomData,
+    }
 }
@@ -0,0 +1,65 @@
+use crate::{
+    graphics,
+    view::{id::ViewId, GeneralEvent, SizeConstraints, TargetedEvent, View, ViewWithoutLayout},
+};
+
+// one of two different views, for when which view is shown depends on the data
+pub(crate) enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+impl<Data, A: ViewWithoutLayout<Data>, B: ViewWithoutLayout<Data>> ViewWithoutLayout<Data> for Either<A, B> {
+    type WithLayout<'without_layout> = Either<A::WithLayout<'without_layout>, B::WithLayout<'without_layout>> where Self: 'without_layout;
+
+    fn layout(&self, sc: SizeConstraints) -> Self::WithLayout<'_> {
+        match self {
+            Either::Left(a) => Either::Left(a.layout(sc)),
+            Either::Right(b) => Either::Right(b.layout(sc)),
+        }
+    }
+}
+
+impl<Data, A: View<Data>, B: View<Data>> View<Data> for Either<A, B> {
+    fn draw_inner(&self, app: &crate::App, target: &mut dyn graphics::RenderTarget, top_left: graphics::Vector2f, hover: Option<ViewId>) {
+        match self {
+            Either::Left(a) => a.draw_inner(app, target, top_left, hover),
+            Either::Right(b) => b.draw_inner(app, target, top_left, hover),
+        }
+    }
+
+    fn find_hover(&self, top_left: graphics::Vector2f, mouse: graphics::Vector2f) -> Option<ViewId> {
+        match self {
+            Either::Left(a) => a.find_hover(top_left, mouse),
+            Either::Right(b) => b.find_hover(top_left, mouse),
+        }
+    }
+
+    fn size(&self) -> graphics::Vector2f {
+        match self {
+            Either::Left(a) => a.size(),
+            Either::Right(b) => b.size(),
+        }
+    }
+
+    fn send_targeted_event(&self, app: &crate::App, data: &mut Data, target: ViewId, event: TargetedEvent) {
+        match self {
+            Either::Left(a) => a.send_targeted_event(app, data, target, event),
+            Either::Right(b) => b.send_targeted_event(app, data, target, event),
+        }
+    }
+
+    fn targeted_event(&self, app: &crate::App, data: &mut Data, event: TargetedEvent) {
+        match self {
+            Either::Left(a) => a.targeted_event(app, data, event),
+            Either::Right(b) => b.targeted_event(app, data, event),
+        }
+    }
+
+    fn general_event(&self, app: &crate::App, data: &mut Data, event: GeneralEvent) {
+        match self {
+            Either::Left(a) => a.general_event(app, data, event),
+            Either::Right(b) => b.general_event(app, data, event),
+        }
+    }
+}
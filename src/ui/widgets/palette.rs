@@ -0,0 +1,176 @@
+use std::{cell::Cell, marker::PhantomData, rc::Rc};
+
+use logicgates::simulation::{edit::NewGate, Simulation};
+use sfml::graphics::{Shape, Transformable};
+
+use crate::{
+    graphics::{self, CenterText},
+    theme::Theme,
+    view::{
+        id::{ViewId, ViewIdMaker},
+        lens::Lens,
+        GeneralEvent, SizeConstraints, TargetedEvent, View, ViewWithoutLayout,
+    },
+};
+
+// list of gates that can be placed, which are dragged out of here and dropped onto a simulation widget
+// the simulation widget is what actually adds the gate, this only keeps track of what is being dragged
+
+pub(crate) struct PaletteState {
+    drag: Option<PaletteDrag>,
+}
+struct PaletteDrag {
+    gate: NewGate,
+    name: String,
+    mouse_pos: graphics::Vector2f,
+    // letting go over the palette puts the gate back instead of dropping it onto whatever is underneath
+    over_palette: bool,
+}
+
+impl PaletteState {
+    pub(crate) fn new() -> PaletteState {
+        PaletteState { drag: None }
+    }
+
+    // the gate being dragged and where it would be dropped, if it is not over the palette
+    pub(crate) fn drop_target(&self) -> Option<(NewGate, graphics::Vector2f)> {
+        self.drag.as_ref().filter(|drag| !drag.over_palette).map(|drag| (drag.gate, drag.mouse_pos))
+    }
+}
+
+struct PaletteView<Data, PaletteLens: Lens<Data, PaletteState>> {
+    palette_lens: PaletteLens,
+    entries: Vec<(ViewId, String, NewGate)>,
+    drag: Option<(String, graphics::Vector2f)>,
+    font: Rc<sfml::SfBox<graphics::Font>>,
+
+    _phantom: PhantomData<fn(&Data)>,
+}
+struct PaletteViewLayout<'original, Data, PaletteLens: Lens<Data, PaletteState>> {
+    view: &'original PaletteView<Data, PaletteLens>,
+    size: graphics::Vector2f,
+    // remembered from find_hover like in the simulation widget, because general events do not say where the palette is
+    top_left: Cell<graphics::Vector2f>,
+}
+
+pub(crate) fn palette<Data>(
+    id_maker: &mut ViewIdMaker,
+    palette_lens: impl Lens<Data, PaletteState>,
+    simulation_lens: impl Lens<Data, Simulation>,
+    font: &Rc<sfml::SfBox<graphics::Font>>,
+    data: &Data,
+) -> impl ViewWithoutLayout<Data> {
+    let primitives = [NewGate::Nand, NewGate::Const(true), NewGate::Const(false), NewGate::Unerror, NewGate::Button, NewGate::TristateBuffer];
+    let entries = simulation_lens.with(data, |simulation| {
+        primitives
+            .into_iter()
+            .map(|gate| (primitive_name(gate).to_string(), gate))
+            .chain(simulation.circuit_definitions().into_iter().map(|ck| (simulation.circuits()[ck].name.clone(), NewGate::CopyOf(ck))))
+            .map(|(name, gate)| (id_maker.next_id(), name, gate))
+            .collect()
+    });
+    let drag = palette_lens.with(data, |palette| palette.drag.as_ref().map(|drag| (drag.name.clone(), drag.mouse_pos)));
+    PaletteView { palette_lens, entries, drag, font: font.clone(), _phantom: PhantomData }
+}
+
+fn primitive_name(gate: NewGate) -> &'static str {
+    match gate {
+        NewGate::Nand => "nand",
+        NewGate::Const(true) => "true",
+        NewGate::Const(false) => "false",
+        NewGate::Unerror => "unerror",
+        NewGate::Button => "button",
+        NewGate::TristateBuffer => "tristate buffer",
+        NewGate::CopyOf(_) => unreachable!("subcircuits are named by their circuits"),
+    }
+}
+
+impl<Data, PaletteLens: Lens<Data, PaletteState>> ViewWithoutLayout<Data> for PaletteView<Data, PaletteLens> {
+    type WithLayout<'without_layout> = PaletteViewLayout<'without_layout, Data, PaletteLens> where Self: 'without_layout;
+
+    fn layout(&self, sc: SizeConstraints) -> Self::WithLayout<'_> {
+        PaletteViewLayout {
+            view: self,
+            size: sc.clamp_size(graphics::Vector2f::new(Theme::DEFAULT.palette_width, self.entries.len() as f32 * Theme::DEFAULT.palette_row_height)),
+            top_left: Cell::new(graphics::Vector2f::new(0.0, 0.0)),
+        }
+    }
+}
+impl<Data, PaletteLens: Lens<Data, PaletteState>> View<Data> for PaletteViewLayout<'_, Data, PaletteLens> {
+    fn draw_inner(&self, _: &crate::App, target: &mut dyn graphics::RenderTarget, top_left: graphics::Vector2f, hover: Option<ViewId>) {
+        let mut background = graphics::RectangleShape::from_rect(graphics::FloatRect::from_vecs(top_left, self.size));
+        background.set_fill_color(Theme::DEFAULT.palette_bg_color);
+        target.draw(&background);
+
+        for (index, (id, name, _)) in self.view.entries.iter().enumerate() {
+            let row_rect = self.row_rect(top_left, index);
+            if Some(*id) == hover {
+                let mut hover_shape = graphics::RectangleShape::from_rect(row_rect);
+                hover_shape.set_fill_color(Theme::DEFAULT.palette_hover_color);
+                target.draw(&hover_shape);
+            }
+            self.draw_label(target, name, row_rect);
+        }
+
+        // the entry being dragged follows the mouse
+        if let Some((name, mouse_pos)) = &self.view.drag {
+            let rect = graphics::FloatRect::new(mouse_pos.x, mouse_pos.y - Theme::DEFAULT.palette_row_height / 2.0, Theme::DEFAULT.palette_width, Theme::DEFAULT.palette_row_height);
+            let mut drag_shape = graphics::RectangleShape::from_rect(rect);
+            drag_shape.set_fill_color(Theme::DEFAULT.palette_hover_color);
+            target.draw(&drag_shape);
+            self.draw_label(target, name, rect);
+        }
+    }
+
+    fn find_hover(&self, top_left: graphics::Vector2f, mouse: graphics::Vector2f) -> Option<ViewId> {
+        self.top_left.set(top_left);
+        (0..self.view.entries.len()).find(|index| self.row_rect(top_left, *index).contains(mouse)).map(|index| self.view.entries[index].0)
+    }
+
+    fn size(&self) -> graphics::Vector2f {
+        self.size
+    }
+
+    fn send_targeted_event(&self, _: &crate::App, data: &mut Data, target: ViewId, event: TargetedEvent) {
+        // the entries are not separate views so they are handled here
+        if let Some((_, name, gate)) = self.view.entries.iter().find(|(id, _, _)| *id == target) {
+            match event {
+                TargetedEvent::LeftMouseDown(mouse_pos) => {
+                    self.view.palette_lens.with_mut(data, |palette| palette.drag = Some(PaletteDrag { gate: *gate, name: name.clone(), mouse_pos, over_palette: true }));
+                }
+                TargetedEvent::RightMouseDown(_) | TargetedEvent::MouseWheelScrolled(_, _) | TargetedEvent::KeyPressed(_) | TargetedEvent::TextEntered(_) => {}
+            }
+        }
+    }
+
+    fn targeted_event(&self, _: &crate::App, _: &mut Data, _: TargetedEvent) {}
+    fn general_event(&self, _: &crate::App, data: &mut Data, event: GeneralEvent) {
+        match event {
+            GeneralEvent::MouseMoved(mouse_pos) => {
+                let over_palette = graphics::FloatRect::from_vecs(self.top_left.get(), self.size).contains(mouse_pos);
+                self.view.palette_lens.with_mut(data, |palette| {
+                    if let Some(drag) = &mut palette.drag {
+                        drag.mouse_pos = mouse_pos;
+                        drag.over_palette = over_palette;
+                    }
+                });
+            }
+            // the simulation widget gets this event first, so it has already dropped the gate if it was going to
+            GeneralEvent::LeftMouseUp => self.view.palette_lens.with_mut(data, |palette| palette.drag = None),
+        }
+    }
+}
+
+impl<Data, PaletteLens: Lens<Data, PaletteState>> PaletteViewLayout<'_, Data, PaletteLens> {
+    fn row_rect(&self, top_left: graphics::Vector2f, index: usize) -> graphics::FloatRect {
+        graphics::FloatRect::new(top_left.x, top_left.y + index as f32 * Theme::DEFAULT.palette_row_height, self.size.x, Theme::DEFAULT.palette_row_height)
+    }
+
+    fn draw_label(&self, target: &mut dyn graphics::RenderTarget, name: &str, rect: graphics::FloatRect) {
+        let mut text = graphics::Text::new(name, &self.view.font, Theme::DEFAULT.palette_font_size);
+        text.set_fill_color(Theme::DEFAULT.palette_text_color);
+        text.center_vertically();
+        text.set_position((rect.left + Theme::DEFAULT.palette_padding, rect.top + rect.height / 2.0));
+        target.draw(&text);
+    }
+}
@@ -1,48 +1,244 @@
-use std::{collections::HashMap, marker::PhantomData, rc::Rc};
+use std::{cell::Cell, collections::HashMap, marker::PhantomData, rc::Rc};
 
+use logicgates::{
+    command::Command,
+    project::Project,
+    simulation::{self, connections, edit::NewGate, hierarchy, logic, Gate, GateKey, NodeKey, NodeMap, Simulation},
+};
 use sfml::graphics::{Shape, Transformable};
 
 use crate::{
     graphics::{self, CenterText, RectCenter},
-    simulation::{self, hierarchy, logic, Gate, GateKey, NodeKey, NodeMap, Simulation},
     theme::Theme,
     view::{
         id::{ViewId, ViewIdMaker},
         lens::Lens,
-        GeneralEvent, SizeConstraints, TargetedEvent, View, ViewWithoutLayout,
+        GeneralEvent, KeyEvent, SizeConstraints, TargetedEvent, View, ViewWithoutLayout,
     },
 };
 
+use super::{
+    palette::PaletteState,
+    waveform::{TracedNodes, WaveformState},
+};
+
+mod routing;
+
 const NODE_SPACING: f32 = 20.0;
 
+// every keyboard shortcut of the simulation widget, which are only matched against in widget_event, so adding or changing one only has to be done here and there
+// they go to the pane that was last clicked on, or the one under the mouse if none was
+// escape               leave the subcircuit being shown
+// f                    fit the circuit to the view
+// delete               remove the selected gates
+// ctrl+c / ctrl+v      copy the selected gates / paste them at the mouse
+// ctrl+g               extract the selected gates into a new subcircuit
+// ctrl+shift+g         inline the selected subcircuits
+// r / shift+r          turn the gate under the mouse clockwise / counterclockwise
+// w                    switch between straight and routed connections
+// + / -                zoom in / out around the middle of the view
+// ctrl+z               undo
+// ctrl+shift+z, ctrl+y redo
+const LEAVE_SUBCIRCUIT_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::Escape);
+const FIT_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::F);
+const REMOVE_GATE_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::Delete);
+const COPY_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::C).ctrl();
+const PASTE_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::V).ctrl();
+const EXTRACT_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::G).ctrl();
+const INLINE_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::G).ctrl().shift();
+const ROTATE_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::R);
+const ROTATE_BACK_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::R).shift();
+const ROUTED_WIRES_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::W);
+const UNDO_SHORTCUT: KeyEvent = KeyEvent::new(sfml::window::Key::Z).ctrl();
+const REDO_SHORTCUTS: [KeyEvent; 2] = [KeyEvent::new(sfml::window::Key::Z).ctrl().shift(), KeyEvent::new(sfml::window::Key::Y).ctrl()];
+// these are matched against the text that is typed instead of the key, because + is on a different key (and needs shift or not) on every keyboard layout
+const ZOOM_IN_TEXT: char = '+';
+const ZOOM_OUT_TEXT: char = '-';
+
 #[derive(Clone)]
 pub(crate) struct SimulationWidgetState {
-    cur_gate_drag: Option<(simulation::GateKey, graphics::Vector2f, (f32, f32))>,
+    cur_gate_drag: Option<GateDrag>,
+    cur_pan: Option<(graphics::Vector2f, graphics::Vector2f)>,
+    cur_wire_drag: Option<WireDrag>,
+    cur_box_select: Option<BoxSelect>,
+    // where the mouse was last, in window coordinates, which is where things are pasted
+    mouse_pos: Option<graphics::Vector2f>,
+    // all in the circuit being shown, and these are what get moved together and removed
+    selected_gates: Vec<GateKey>,
     view_stack: Vec<simulation::CircuitKey>,
+    // None means fit the whole circuit into the widget, which is what happens until the camera is moved by hand
+    camera: Option<Camera>,
+    // connections go around gates instead of straight from one node to the other
+    routed_wires: bool,
+    route_cache: routing::RouteCache,
 }
 
 impl SimulationWidgetState {
     pub(crate) fn new() -> SimulationWidgetState {
-        SimulationWidgetState { cur_gate_drag: None, view_stack: Vec::new() }
+        SimulationWidgetState {
+            cur_gate_drag: None,
+            cur_pan: None,
+            cur_wire_drag: None,
+            cur_box_select: None,
+            mouse_pos: None,
+            selected_gates: Vec::new(),
+            view_stack: Vec::new(),
+            camera: None,
+            routed_wires: false,
+            route_cache: routing::RouteCache::new(),
+        }
+    }
+
+    // any edit or undo can remove things that the state refers to
+    fn forget_removed(&mut self, simulation: &Simulation) {
+        let depth = self.view_stack.iter().take_while(|ck| simulation.circuits().contains_key(**ck)).count();
+        if depth != self.view_stack.len() {
+            self.view_stack.truncate(depth);
+            self.camera = None;
+        }
+        self.selected_gates.retain(|gate| simulation.gates().contains_key(*gate));
+        if let Some(gate_drag) = &mut self.cur_gate_drag {
+            gate_drag.gates.retain(|(gate, _)| simulation.gates().contains_key(*gate));
+        }
+        if self.cur_wire_drag.is_some_and(|wire_drag| !simulation.nodes().contains_key(wire_drag.from)) {
+            self.cur_wire_drag = None;
+        }
+    }
+}
+
+// every selected gate moves by how far the mouse moved since the drag started
+#[derive(Clone)]
+struct GateDrag {
+    mouse_start: graphics::Vector2f,
+    // the gate that was clicked on, which is the one that snaps to the grid (the others keep where they are relative to it)
+    gate: GateKey,
+    // every gate that is being moved and where it started
+    gates: Vec<(GateKey, (f32, f32))>,
+}
+
+// a rectangle dragged out with shift held down, which selects every gate inside of it
+#[derive(Copy, Clone)]
+struct BoxSelect {
+    // both in circuit coordinates
+    start: graphics::Vector2f,
+    end: graphics::Vector2f,
+}
+impl BoxSelect {
+    fn rect(&self) -> graphics::FloatRect {
+        graphics::FloatRect::new(self.start.x.min(self.end.x), self.start.y.min(self.end.y), (self.start.x - self.end.x).abs(), (self.start.y - self.end.y).abs())
+    }
+}
+
+// a connection being dragged out of a node, which gets connected to whatever node it is let go on
+#[derive(Copy, Clone)]
+struct WireDrag {
+    from: NodeKey,
+    // in circuit coordinates
+    mouse_pos: graphics::Vector2f,
+}
+
+// gates are drawn in circuit coordinates (the coordinates in GateLocation) and the camera decides which part of that is shown in the widget
+// center is the point in circuit coordinates that is in the middle of the widget, and zoom is how many pixels one unit in circuit coordinates takes up
+#[derive(Copy, Clone)]
+struct Camera {
+    center: graphics::Vector2f,
+    zoom: f32,
+}
+
+impl Camera {
+    fn new() -> Camera {
+        Camera { center: graphics::Vector2f::new(0.0, 0.0), zoom: 1.0 }
+    }
+
+    fn fit(bounds: Option<graphics::FloatRect>, widget_size: graphics::Vector2f) -> Camera {
+        match bounds {
+            Some(bounds) => {
+                let padding = Theme::DEFAULT.camera_fit_padding * 2.0;
+                let zoom = f32::min((widget_size.x - padding) / bounds.width, (widget_size.y - padding) / bounds.height);
+                Camera { center: bounds.center(), zoom: zoom.clamp(Theme::DEFAULT.camera_min_zoom, Theme::DEFAULT.camera_max_zoom) }
+            }
+            None => Camera::new(),
+        }
+    }
+
+    // the part of the circuit that is shown, in circuit coordinates
+    fn visible_rect(&self, widget_size: graphics::Vector2f) -> graphics::FloatRect {
+        let size = widget_size / self.zoom;
+        graphics::FloatRect::from_vecs(self.center - size / 2.0, size)
+    }
+
+    fn screen_to_circuit(&self, widget_rect: graphics::FloatRect, screen_pos: graphics::Vector2f) -> graphics::Vector2f {
+        self.center + (screen_pos - widget_rect.center()) / self.zoom
+    }
+
+    // zooms while keeping the point at around_pos (in circuit coordinates) in the same place on the screen
+    fn zoom_around(&mut self, around_pos: graphics::Vector2f, factor: f32) {
+        let new_zoom = (self.zoom * factor).clamp(Theme::DEFAULT.camera_min_zoom, Theme::DEFAULT.camera_max_zoom);
+        self.center = around_pos - (around_pos - self.center) * (self.zoom / new_zoom);
+        self.zoom = new_zoom;
     }
 }
 
-struct SimulationView<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> {
+struct SimulationView<
+    Data,
+    StateLens: Lens<Data, SimulationWidgetState>,
+    SimulationLens: Lens<Data, Simulation>,
+    ProjectLens: Lens<Data, Project>,
+    WaveformLens: Lens<Data, WaveformState>,
+    PaletteLens: Lens<Data, PaletteState>,
+> {
     id: ViewId,
 
     state_lens: StateLens,
+    simulation_lens: SimulationLens,
+    project_lens: ProjectLens,
+    palette_lens: PaletteLens,
+    // the circuit being shown, or None for the toplevel
+    current_view: Option<simulation::CircuitKey>,
+    camera: Option<Camera>,
+    wire_drag: Option<WireDrag>,
+    box_select: Option<BoxSelect>,
+    // the gates being dragged, which alignment guides are shown for
+    dragged_gates: Vec<GateKey>,
+    // None if connections are drawn straight
+    route_cache: Option<routing::RouteCache>,
+    // bounding box of all of the gates being shown, in circuit coordinates
+    bounds: Option<graphics::FloatRect>,
+    fit_button_id: ViewId,
+    // the toplevel and then every circuit in the view stack
+    breadcrumbs: Vec<(ViewId, String)>,
+    font: Rc<sfml::SfBox<graphics::Font>>,
 
     gates: Vec<GateView<Data, StateLens, SimulationLens>>,
-    nodes: Vec<NodeView<Data, StateLens, SimulationLens>>,
-    connections: Vec<ConnectionView<Data, StateLens, SimulationLens>>,
+    nodes: Vec<NodeView<Data, StateLens, SimulationLens, WaveformLens>>,
+    connections: Vec<ConnectionView<Data, ProjectLens>>,
+    // first and last node of every bundle that is being shown
+    bundles: Vec<(NodeViewPos, NodeViewPos)>,
+    // how many nodes are producing something other than Z in every net that is being shown, indexed by the net of a node or connection view
+    net_drivers: Vec<usize>,
 }
-struct SimulationViewLayout<'original, Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> {
-    view: &'original SimulationView<Data, StateLens, SimulationLens>,
+struct SimulationViewLayout<
+    'original,
+    Data,
+    StateLens: Lens<Data, SimulationWidgetState>,
+    SimulationLens: Lens<Data, Simulation>,
+    ProjectLens: Lens<Data, Project>,
+    WaveformLens: Lens<Data, WaveformState>,
+    PaletteLens: Lens<Data, PaletteState>,
+> {
+    view: &'original SimulationView<Data, StateLens, SimulationLens, ProjectLens, WaveformLens, PaletteLens>,
     widget_size: graphics::Vector2f,
+    camera: Camera,
+    // events do not come with where the widget is, so this is remembered from find_hover, which always happens before a mouse event is sent
+    top_left: Cell<graphics::Vector2f>,
+    // relative to the top left of the widget
+    breadcrumb_rects: Vec<graphics::FloatRect>,
 
     gates: Vec<GateViewLayout<'original, Data, StateLens, SimulationLens>>,
-    nodes: Vec<NodeViewLayout<'original, Data, StateLens, SimulationLens>>,
-    connections: Vec<ConnectionViewLayout<'original, Data, StateLens, SimulationLens>>,
+    nodes: Vec<NodeViewLayout<'original, Data, StateLens, SimulationLens, WaveformLens>>,
+    connections: Vec<ConnectionViewLayout<'original, Data, ProjectLens>>,
+    // in circuit coordinates
+    alignment_guides: Vec<(graphics::Vector2f, graphics::Vector2f)>,
 }
 
 struct GateView<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> {
@@ -60,7 +256,7 @@ struct GateView<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLe
 
     kind: GateViewKind,
 
-    being_dragged: bool,
+    selected: bool,
 
     ck_to_zoom: Option<simulation::CircuitKey>,
 
@@ -72,9 +268,10 @@ enum GateViewKind {
     Normal,
     Button(graphics::Color),
 }
+// the layouts of everything inside the simulation widget work in circuit coordinates
 struct GateViewLayout<'original, Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> {
     view: &'original GateView<Data, StateLens, SimulationLens>,
-    widget_size: graphics::Vector2f,
+    camera: Camera,
 }
 #[derive(Copy, Clone)]
 enum NodeViewPos {
@@ -83,63 +280,80 @@ enum NodeViewPos {
     GateInput { gate_pos: (f32, f32), gate_direction: simulation::GateDirection, index: usize, num_inputs: usize, num_outputs: usize },
     GateOutput { gate_pos: (f32, f32), gate_direction: simulation::GateDirection, index: usize, num_inputs: usize, num_outputs: usize },
 }
-struct NodeView<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> {
+struct NodeView<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>, WaveformLens: Lens<Data, WaveformState>> {
     id: ViewId,
 
-    // state_lens: StateLens,
-    // simulation_lens: SimulationLens,
+    state_lens: StateLens,
+    simulation_lens: SimulationLens,
+    waveform_lens: WaveformLens,
 
-    // key: NodeKey,
+    key: NodeKey,
     pos: NodeViewPos,
     color: graphics::Color,
+    label: Option<String>,
+    net: usize,
+
+    font: Rc<sfml::SfBox<graphics::Font>>,
 
     _phantom: PhantomData<fn(&Data)>,
-    _phantom2: PhantomData<StateLens>,
-    _phantom3: PhantomData<SimulationLens>,
 }
-struct NodeViewLayout<'original, Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> {
-    view: &'original NodeView<Data, StateLens, SimulationLens>,
-    widget_size: graphics::Vector2f,
+struct NodeViewLayout<'original, Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>, WaveformLens: Lens<Data, WaveformState>> {
+    view: &'original NodeView<Data, StateLens, SimulationLens, WaveformLens>,
+    visible_rect: graphics::FloatRect,
 }
-struct ConnectionView<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> {
+struct ConnectionView<Data, ProjectLens: Lens<Data, Project>> {
     id: ViewId,
 
-    // state_lens: StateLens,
-    // simulation_lens: SimulationLens,
+    project_lens: ProjectLens,
 
-    // node1: NodeKey,
-    // node2: NodeKey,
+    node1: NodeKey,
+    node2: NodeKey,
     pos1: NodeViewPos,
     pos2: NodeViewPos,
     color: graphics::Color,
+    net: usize,
 
     _phantom: PhantomData<fn(&Data)>,
-    _phantom2: PhantomData<StateLens>,
-    _phantom3: PhantomData<SimulationLens>,
 }
-struct ConnectionViewLayout<'original, Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> {
-    view: &'original ConnectionView<Data, StateLens, SimulationLens>,
-    widget_size: graphics::Vector2f,
+struct ConnectionViewLayout<'original, Data, ProjectLens: Lens<Data, Project>> {
+    view: &'original ConnectionView<Data, ProjectLens>,
+    // every corner of the line, starting at node1 and ending at node2
+    points: Rc<[graphics::Vector2f]>,
 }
 
+// id is the id of the whole widget, which is the one that takes focus, so it has to stay the same when the views are made again (see ViewId::keyed)
 pub(crate) fn simulation<Data>(
     id_maker: &mut ViewIdMaker,
+    id: ViewId,
     state_lens: impl Lens<Data, SimulationWidgetState> + Copy,
     simulation_lens: impl Lens<Data, Simulation> + Copy,
+    project_lens: impl Lens<Data, Project> + Copy,
+    waveform_lens: impl Lens<Data, WaveformState> + Copy,
+    palette_lens: impl Lens<Data, PaletteState>,
     font: &Rc<sfml::SfBox<graphics::Font>>,
     data: &Data,
 ) -> impl ViewWithoutLayout<Data> {
-    // TODO: show currently viewing at top of widget
-    let (current_view, cur_gate_drag) = state_lens.with(data, |state| (state.view_stack.last().copied(), state.cur_gate_drag));
-    let (gates, nodes, connections) = simulation_lens.with(data, |simulation| {
+    let (camera, wire_drag, box_select, selected_gates, view_stack, dragged_gates, route_cache) = state_lens.with(data, |state| {
+        let dragged_gates = state.cur_gate_drag.as_ref().map(|gate_drag| gate_drag.gates.iter().map(|(gate, _)| *gate).collect()).unwrap_or_default();
+        let route_cache = state.routed_wires.then(|| state.route_cache.shared());
+        (state.camera, state.cur_wire_drag, state.cur_box_select, state.selected_gates.clone(), state.view_stack.clone(), dragged_gates, route_cache)
+    });
+    // a circuit can be removed through another simulation pane while this one is inside of it, in which case this one shows the circuit that it was in
+    let view_stack: Vec<_> = simulation_lens.with(data, |simulation| view_stack.into_iter().take_while(|ck| simulation.circuits().contains_key(*ck)).collect());
+    let current_view = view_stack.last().copied();
+    let breadcrumbs = std::iter::once(simulation::path::TOPLEVEL_NAME.to_string())
+        .chain(simulation_lens.with(data, |simulation| view_stack.iter().map(|ck| simulation.circuits()[*ck].name.clone()).collect::<Vec<_>>()))
+        .map(|name| (id_maker.next_id(), name))
+        .collect();
+    let (gates, nodes, connections, bundles, net_drivers, bounds) = simulation_lens.with(data, |simulation| {
         let gates_currently_viewing = match current_view {
-            Some(ck) => &simulation.circuits[ck].gates,
-            None => &simulation.toplevel_gates,
+            Some(ck) => &simulation.circuits()[ck].gates,
+            None => simulation.toplevel_gates(),
         };
 
         let extra_nodes = match current_view {
             Some(ck) => {
-                let circuit = &simulation.circuits[ck];
+                let circuit = &simulation.circuits()[ck];
                 Some(circuit.nodes.inputs().iter().chain(circuit.nodes.outputs().iter()))
             }
             None => None,
@@ -147,18 +361,46 @@ pub(crate) fn simulation<Data>(
         let gates = gates_currently_viewing.iter().copied();
         let nodes = gates_currently_viewing
             .iter()
-            .flat_map(|gate| simulation::Gate::inputs(&simulation.circuits, &simulation.gates, *gate).iter().chain(simulation::Gate::outputs(&simulation.circuits, &simulation.gates, *gate)))
+            .flat_map(|gate| simulation::Gate::inputs(simulation.circuits(), simulation.gates(), *gate).iter().chain(simulation::Gate::outputs(simulation.circuits(), simulation.gates(), *gate)))
             .chain(extra_nodes.into_iter().flatten())
             .copied();
 
+        let bundle_nodes: Vec<_> = gates_currently_viewing
+            .iter()
+            .flat_map(|gate| {
+                [
+                    (Gate::inputs(simulation.circuits(), simulation.gates(), *gate), Gate::input_names(simulation.circuits(), simulation.gates(), *gate)),
+                    (Gate::outputs(simulation.circuits(), simulation.gates(), *gate), Gate::output_names(simulation.circuits(), simulation.gates(), *gate)),
+                ]
+            })
+            .chain(current_view.into_iter().flat_map(|ck| {
+                let circuit = &simulation.circuits()[ck];
+                [(&circuit.nodes.inputs()[..], circuit.nodes.input_names()), (&circuit.nodes.outputs()[..], circuit.nodes.output_names())]
+            }))
+            .flat_map(|(nodes, names)| names.bundles().iter().filter(|bundle| bundle.size > 0).map(|bundle| (nodes[bundle.start], nodes[bundle.start + bundle.size - 1])))
+            .collect();
+
+        let bounds = gates_currently_viewing
+            .iter()
+            .map(|gate| {
+                let location = Gate::location(simulation.circuits(), simulation.gates(), *gate);
+                let direction = Gate::direction(simulation.circuits(), simulation.gates(), *gate);
+                gate_rect((location.x, location.y), direction, Gate::num_inputs(simulation.circuits(), simulation.gates(), *gate), Gate::num_outputs(simulation.circuits(), simulation.gates(), *gate))
+            })
+            .reduce(|a, b| {
+                let left = a.left.min(b.left);
+                let top = a.top.min(b.top);
+                graphics::FloatRect::new(left, top, (a.left + a.width).max(b.left + b.width) - left, (a.top + a.height).max(b.top + b.height) - top)
+            });
+
         let gate_views = gates
             .into_iter()
             .map(|gate| {
-                let gate_location = Gate::location(&simulation.circuits, &simulation.gates, gate);
-                let num_inputs = Gate::num_inputs(&simulation.circuits, &simulation.gates, gate);
-                let num_outputs = Gate::num_outputs(&simulation.circuits, &simulation.gates, gate);
-                let direction = Gate::direction(&simulation.circuits, &simulation.gates, gate);
-                let gate_name = simulation.gates[gate].name(&simulation.circuits).to_string();
+                let gate_location = Gate::location(simulation.circuits(), simulation.gates(), gate);
+                let num_inputs = Gate::num_inputs(simulation.circuits(), simulation.gates(), gate);
+                let num_outputs = Gate::num_outputs(simulation.circuits(), simulation.gates(), gate);
+                let direction = Gate::direction(simulation.circuits(), simulation.gates(), gate);
+                let gate_name = simulation.gates()[gate].name(simulation.circuits()).to_string();
 
                 GateView {
                     id: id_maker.next_id(),
@@ -170,40 +412,43 @@ pub(crate) fn simulation<Data>(
                     direction,
                     num_inputs,
                     num_outputs,
-                    kind: match &simulation.gates[gate] {
+                    kind: match &simulation.gates()[gate] {
                         Gate::Nand { logic: _, location: _, direction: _ }
                         | Gate::Const { logic: _, location: _, direction: _ }
                         | Gate::Unerror { logic: _, location: _, direction: _ }
                         | Gate::TristateBuffer { logic: _, location: _, direction: _ }
                         | Gate::Custom(_) => GateViewKind::Normal,
-                        Gate::Button { logic, location: _, direction: _ } => GateViewKind::Button(node_color(&simulation.nodes, logic.nodes.outputs()[0], true)),
+                        Gate::Button { logic, location: _, direction: _ } => GateViewKind::Button(node_color(simulation.nodes(), logic.nodes.outputs()[0], true)),
                     },
-                    being_dragged: if let Some((cur_gate_drag, _, _)) = cur_gate_drag { cur_gate_drag == gate } else { false },
-                    ck_to_zoom: if let Gate::Custom(ck) = &simulation.gates[gate] { Some(*ck) } else { None },
+                    selected: selected_gates.contains(&gate),
+                    ck_to_zoom: if let Gate::Custom(ck) = &simulation.gates()[gate] { Some(*ck) } else { None },
                     font: font.clone(),
                     _phantom: PhantomData,
                 }
             })
             .collect();
 
+        // nets also go through nodes that are not being shown (like the ones inside of subcircuits), which count as drivers too
+        let mut node_nets = HashMap::new();
+        let mut net_drivers = Vec::new();
         let node_positions_and_colors: HashMap<_, _> = nodes
             .into_iter()
             .map(|node| {
-                let pos = match simulation.nodes[node].parent.kind() {
+                let pos = match simulation.nodes()[node].parent.kind() {
                     hierarchy::NodeParentKind::CircuitIn(c, i) if Some(c) == current_view => {
-                        let circuit = &simulation.circuits[c];
+                        let circuit = &simulation.circuits()[c];
                         let num_inputs = circuit.nodes.inputs().len();
                         let num_outputs = circuit.nodes.outputs().len();
                         NodeViewPos::FarLeftEdge { index: i, num_inputs, num_outputs }
                     }
                     hierarchy::NodeParentKind::CircuitOut(c, i) if Some(c) == current_view => {
-                        let circuit = &simulation.circuits[c];
+                        let circuit = &simulation.circuits()[c];
                         let num_inputs = circuit.nodes.inputs().len();
                         let num_outputs = circuit.nodes.outputs().len();
                         NodeViewPos::FarRightEdge { index: i, num_inputs, num_outputs }
                     }
                     hierarchy::NodeParentKind::CircuitIn(c, i) => {
-                        let circuit = &simulation.circuits[c];
+                        let circuit = &simulation.circuits()[c];
                         let location = &circuit.location;
                         let num_inputs = circuit.nodes.inputs().len();
                         let num_outputs = circuit.nodes.outputs().len();
@@ -211,7 +456,7 @@ pub(crate) fn simulation<Data>(
                         NodeViewPos::GateInput { gate_pos: (location.x, location.y), gate_direction: direction, index: i, num_inputs, num_outputs }
                     }
                     hierarchy::NodeParentKind::CircuitOut(c, i) => {
-                        let circuit = &simulation.circuits[c];
+                        let circuit = &simulation.circuits()[c];
                         let location = &circuit.location;
                         let num_inputs = circuit.nodes.inputs().len();
                         let num_outputs = circuit.nodes.outputs().len();
@@ -219,27 +464,41 @@ pub(crate) fn simulation<Data>(
                         NodeViewPos::GateOutput { gate_pos: (location.x, location.y), gate_direction: direction, index: i, num_inputs, num_outputs }
                     }
                     hierarchy::NodeParentKind::GateIn(g, i) => {
-                        let location = &simulation::Gate::location(&simulation.circuits, &simulation.gates, g);
-                        let num_inputs = simulation::Gate::num_inputs(&simulation.circuits, &simulation.gates, g);
-                        let num_outputs = simulation::Gate::num_outputs(&simulation.circuits, &simulation.gates, g);
-                        let direction = simulation::Gate::direction(&simulation.circuits, &simulation.gates, g);
+                        let location = &simulation::Gate::location(simulation.circuits(), simulation.gates(), g);
+                        let num_inputs = simulation::Gate::num_inputs(simulation.circuits(), simulation.gates(), g);
+                        let num_outputs = simulation::Gate::num_outputs(simulation.circuits(), simulation.gates(), g);
+                        let direction = simulation::Gate::direction(simulation.circuits(), simulation.gates(), g);
                         NodeViewPos::GateInput { gate_pos: (location.x, location.y), gate_direction: direction, index: i, num_inputs, num_outputs }
                     }
                     hierarchy::NodeParentKind::GateOut(g, i) => {
-                        let location = &simulation::Gate::location(&simulation.circuits, &simulation.gates, g);
-                        let num_inputs = simulation::Gate::num_inputs(&simulation.circuits, &simulation.gates, g);
-                        let num_outputs = simulation::Gate::num_outputs(&simulation.circuits, &simulation.gates, g);
-                        let direction = simulation::Gate::direction(&simulation.circuits, &simulation.gates, g);
+                        let location = &simulation::Gate::location(simulation.circuits(), simulation.gates(), g);
+                        let num_inputs = simulation::Gate::num_inputs(simulation.circuits(), simulation.gates(), g);
+                        let num_outputs = simulation::Gate::num_outputs(simulation.circuits(), simulation.gates(), g);
+                        let direction = simulation::Gate::direction(simulation.circuits(), simulation.gates(), g);
                         NodeViewPos::GateOutput { gate_pos: (location.x, location.y), gate_direction: direction, index: i, num_inputs, num_outputs }
                     }
                 };
-                let color = node_color(&simulation.nodes, node, true);
+                let color = node_color(simulation.nodes(), node, true);
+                let label = simulation.node_name(node).map(str::to_string);
+                let net = match node_nets.get(&node) {
+                    Some(net) => *net,
+                    None => {
+                        let members = connections::net_members(simulation.nodes(), node);
+                        let drivers = members.iter().filter(|member| logic::get_node_production(simulation.nodes(), **member).is_some_and(|production| production != logic::Value::Z)).count();
+                        net_drivers.push(drivers);
+                        let net = net_drivers.len() - 1;
+                        for member in members {
+                            node_nets.insert(member, net);
+                        }
+                        net
+                    }
+                };
 
-                (node, (pos, color))
+                (node, (pos, color, label, net))
             })
             .collect();
         let connection_vews: Vec<_> = simulation
-            .connections
+            .connections()
             .iter()
             .filter_map(|(a, b)| {
                 Some(ConnectionView {
@@ -247,48 +506,135 @@ pub(crate) fn simulation<Data>(
                     pos1: node_positions_and_colors.get(a)?.0,
                     pos2: node_positions_and_colors.get(b)?.0,
                     color: node_positions_and_colors.get(a)?.1,
+                    net: node_positions_and_colors.get(a)?.3,
                     _phantom: PhantomData,
-                    _phantom2: PhantomData,
-                    _phantom3: PhantomData,
-                    // state_lens,
-                    // simulation_lens,
-                    // node1: *a,
-                    // node2: *b,
+                    project_lens,
+                    node1: *a,
+                    node2: *b,
                 })
             })
             .collect();
+        let bundle_views = bundle_nodes.into_iter().filter_map(|(first, last)| Some((node_positions_and_colors.get(&first)?.0, node_positions_and_colors.get(&last)?.0))).collect();
         let node_views = node_positions_and_colors
             .into_iter()
-            .map(|(_, (pos, color))| NodeView {
+            .map(|(node, (pos, color, label, net))| NodeView {
                 id: id_maker.next_id(),
+                state_lens,
+                simulation_lens,
+                waveform_lens,
+                key: node,
                 pos,
                 color,
+                label,
+                net,
+                font: font.clone(),
                 _phantom: PhantomData,
-                _phantom2: PhantomData,
-                _phantom3: PhantomData, /* state_lens, simulation_lens, key: node */
             })
             .collect();
 
-        (gate_views, node_views, connection_vews)
+        (gate_views, node_views, connection_vews, bundle_views, net_drivers, bounds)
     });
 
-    SimulationView { id: id_maker.next_id(), state_lens, gates, nodes, connections }
+    SimulationView {
+        id,
+        state_lens,
+        simulation_lens,
+        project_lens,
+        palette_lens,
+        current_view,
+        camera,
+        wire_drag,
+        box_select,
+        dragged_gates,
+        route_cache,
+        bounds,
+        fit_button_id: id_maker.next_id(),
+        breadcrumbs,
+        font: font.clone(),
+        gates,
+        nodes,
+        connections,
+        bundles,
+        net_drivers,
+    }
 }
 
-impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> ViewWithoutLayout<Data> for SimulationView<Data, StateLens, SimulationLens> {
-    type WithLayout<'without_layout> = SimulationViewLayout<'without_layout, Data, StateLens, SimulationLens> where Self: 'without_layout;
+impl<
+        Data,
+        StateLens: Lens<Data, SimulationWidgetState>,
+        SimulationLens: Lens<Data, Simulation>,
+        ProjectLens: Lens<Data, Project>,
+        WaveformLens: Lens<Data, WaveformState>,
+        PaletteLens: Lens<Data, PaletteState>,
+    > ViewWithoutLayout<Data> for SimulationView<Data, StateLens, SimulationLens, ProjectLens, WaveformLens, PaletteLens>
+{
+    type WithLayout<'without_layout> = SimulationViewLayout<'without_layout, Data, StateLens, SimulationLens, ProjectLens, WaveformLens, PaletteLens> where Self: 'without_layout;
 
     fn layout(&self, sc: SizeConstraints) -> Self::WithLayout<'_> {
+        let camera = self.camera.unwrap_or_else(|| Camera::fit(self.bounds, sc.max));
+        let visible_rect = camera.visible_rect(sc.max);
+        let (dragged, others): (Vec<_>, Vec<_>) = self.gates.iter().partition(|gate| self.dragged_gates.contains(&gate.gate_key));
+        let alignment_lines_of = |gates: Vec<&GateView<_, _, _>>| {
+            gates.into_iter().map(|gate| alignment_lines(gate.gate_location, gate.direction, gate.num_inputs, gate.num_outputs)).collect::<Vec<_>>()
+        };
+        let alignment_guides = if dragged.is_empty() {
+            Vec::new()
+        } else {
+            alignment_guides(&alignment_lines_of(dragged), &alignment_lines_of(others), Theme::DEFAULT.alignment_guide_dist / camera.zoom)
+        };
+        let connections = match &self.route_cache {
+            Some(route_cache) => {
+                let gate_rects: Vec<_> = self.gates.iter().map(|gate| gate_rect(gate.gate_location, gate.direction, gate.num_inputs, gate.num_outputs)).collect();
+                let connections = self
+                    .connections
+                    .iter()
+                    .map(|connection| {
+                        let from = (node_pos(visible_rect, connection.pos1), -node_inward_direction(connection.pos1));
+                        let to = (node_pos(visible_rect, connection.pos2), -node_inward_direction(connection.pos2));
+                        ConnectionViewLayout { view: connection, points: route_cache.route((connection.node1, connection.node2), from, to, &gate_rects) }
+                    })
+                    .collect();
+                route_cache.forget_unused();
+                connections
+            }
+            None => self
+                .connections
+                .iter()
+                .map(|connection| ConnectionViewLayout { view: connection, points: [node_pos(visible_rect, connection.pos1), node_pos(visible_rect, connection.pos2)].into() })
+                .collect(),
+        };
+        // the gates, nodes, and connections are not laid out through ViewWithoutLayout because they need to know about the camera
         SimulationViewLayout {
             view: self,
             widget_size: sc.max,
-            gates: self.gates.iter().map(|gate| gate.layout(sc)).collect(),
-            nodes: self.nodes.iter().map(|node| node.layout(sc)).collect(),
-            connections: self.connections.iter().map(|connection| connection.layout(sc)).collect(),
+            camera,
+            top_left: Cell::new(graphics::Vector2f::new(0.0, 0.0)),
+            breadcrumb_rects: self
+                .breadcrumbs
+                .iter()
+                .scan(0.0, |x, (_, name)| {
+                    let width = graphics::Text::new(name, &self.font, Theme::DEFAULT.breadcrumb_font_size).local_bounds().width + Theme::DEFAULT.breadcrumb_padding * 2.0;
+                    let rect = graphics::FloatRect::new(*x, 0.0, width, Theme::DEFAULT.breadcrumb_bar_height);
+                    *x += width + Theme::DEFAULT.breadcrumb_separator_width;
+                    Some(rect)
+                })
+                .collect(),
+            gates: self.gates.iter().map(|gate| GateViewLayout { view: gate, camera }).collect(),
+            nodes: self.nodes.iter().map(|node| NodeViewLayout { view: node, visible_rect }).collect(),
+            connections,
+            alignment_guides,
         }
     }
 }
-impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, Simulation>> View<Data> for SimulationViewLayout<'_, Data, StateLens, SimulationLens> {
+impl<
+        Data,
+        StateLens: Lens<Data, SimulationWidgetState>,
+        SimulationLens: Lens<Data, Simulation>,
+        ProjectLens: Lens<Data, Project>,
+        WaveformLens: Lens<Data, WaveformState>,
+        PaletteLens: Lens<Data, PaletteState>,
+    > View<Data> for SimulationViewLayout<'_, Data, StateLens, SimulationLens, ProjectLens, WaveformLens, PaletteLens>
+{
     fn draw_inner(&self, app: &crate::App, target: &mut dyn graphics::RenderTarget, top_left: graphics::Vector2f, hover: Option<ViewId>) {
         let widget_rect = graphics::FloatRect::from_vecs(top_left, self.widget_size);
 
@@ -296,18 +642,172 @@ impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Da
         widget_shape.set_fill_color(Theme::DEFAULT.simulation_bg_color);
         target.draw(&widget_shape);
 
+        // everything in the circuit is drawn through the camera, which also clips it to the widget
+        let old_view = target.view().to_owned();
+        let target_size: graphics::Vector2f = target.size().as_other();
+        let visible_rect = self.camera.visible_rect(self.widget_size);
+        let mut camera_view = graphics::View::from_rect(visible_rect);
+        camera_view.set_viewport(graphics::FloatRect::new(top_left.x / target_size.x, top_left.y / target_size.y, self.widget_size.x / target_size.x, self.widget_size.y / target_size.y));
+        target.set_view(&camera_view);
+
+        // the major lines are drawn over the minor ones, and either is left out when zoomed out far enough that they would be too close together
+        for (spacing, color) in [
+            (Theme::DEFAULT.grid_spacing, Theme::DEFAULT.grid_color),
+            (Theme::DEFAULT.grid_spacing * Theme::DEFAULT.grid_major_every as f32, Theme::DEFAULT.grid_major_color),
+        ] {
+            if spacing * self.camera.zoom < Theme::DEFAULT.grid_min_line_spacing {
+                continue;
+            }
+            let (right, bottom) = (visible_rect.left + visible_rect.width, visible_rect.top + visible_rect.height);
+            let mut x = (visible_rect.left / spacing).ceil() * spacing;
+            while x < right {
+                draw_line(target, graphics::Vector2f::new(x, visible_rect.top), graphics::Vector2f::new(x, bottom), 1.0 / self.camera.zoom, color);
+                x += spacing;
+            }
+            let mut y = (visible_rect.top / spacing).ceil() * spacing;
+            while y < bottom {
+                draw_line(target, graphics::Vector2f::new(visible_rect.left, y), graphics::Vector2f::new(right, y), 1.0 / self.camera.zoom, color);
+                y += spacing;
+            }
+        }
+
         for connection in &self.connections {
             connection.draw(app, target, top_left, hover);
         }
         for gate in &self.gates {
             gate.draw(app, target, top_left, hover);
         }
+        for (first, last) in &self.view.bundles {
+            // outline around all of the nodes in the bundle
+            let first = node_pos(visible_rect, *first);
+            let last = node_pos(visible_rect, *last);
+            let rad = Theme::DEFAULT.node_rad + Theme::DEFAULT.node_hover_dist;
+            let mut bundle_shape = graphics::RectangleShape::from_rect(graphics::FloatRect::new(
+                first.x.min(last.x) - rad,
+                first.y.min(last.y) - rad,
+                (last.x - first.x).abs() + rad * 2.0,
+                (last.y - first.y).abs() + rad * 2.0,
+            ));
+            bundle_shape.set_fill_color(graphics::Color::TRANSPARENT);
+            bundle_shape.set_outline_color(Theme::DEFAULT.bundle_outline_color);
+            bundle_shape.set_outline_thickness(1.0);
+            target.draw(&bundle_shape);
+        }
+        if let Some(wire_drag) = self.view.wire_drag {
+            if let Some(from) = self.nodes.iter().find(|node| node.view.key == wire_drag.from) {
+                draw_line(target, node_pos(visible_rect, from.view.pos), wire_drag.mouse_pos, Theme::DEFAULT.connection_width, from.view.color);
+            }
+        }
         for node in &self.nodes {
             node.draw(app, target, top_left, hover);
         }
+        // hovering over a node or a connection dims everything else and draws its whole net again on top, with every part of it drawn like it is hovered over
+        let hovered_net = self
+            .nodes
+            .iter()
+            .find(|node| Some(node.view.id) == hover)
+            .map(|node| node.view.net)
+            .or_else(|| self.connections.iter().find(|connection| Some(connection.view.id) == hover).map(|connection| connection.view.net));
+        if let Some(net) = hovered_net {
+            let mut dim_shape = graphics::RectangleShape::from_rect(visible_rect);
+            dim_shape.set_fill_color(Theme::DEFAULT.net_dim_color);
+            target.draw(&dim_shape);
+            for connection in self.connections.iter().filter(|connection| connection.view.net == net) {
+                connection.draw(app, target, top_left, Some(connection.view.id));
+            }
+            for node in self.nodes.iter().filter(|node| node.view.net == net) {
+                node.draw(app, target, top_left, Some(node.view.id));
+            }
+        }
+        for (start, end) in &self.alignment_guides {
+            draw_line(target, *start, *end, 1.0 / self.camera.zoom, Theme::DEFAULT.alignment_guide_color);
+        }
+        if let Some(box_select) = self.view.box_select {
+            let mut box_shape = graphics::RectangleShape::from_rect(box_select.rect());
+            box_shape.set_fill_color(Theme::DEFAULT.box_select_color);
+            box_shape.set_outline_color(Theme::DEFAULT.box_select_outline_color);
+            box_shape.set_outline_thickness(1.0 / self.camera.zoom);
+            target.draw(&box_shape);
+        }
+
+        target.set_view(&old_view);
+
+        let mut breadcrumb_bar_shape = graphics::RectangleShape::from_rect(graphics::FloatRect::new(top_left.x, top_left.y, self.widget_size.x, Theme::DEFAULT.breadcrumb_bar_height));
+        breadcrumb_bar_shape.set_fill_color(Theme::DEFAULT.breadcrumb_bg_color);
+        target.draw(&breadcrumb_bar_shape);
+        for (i, ((id, name), rect)) in self.view.breadcrumbs.iter().zip(&self.breadcrumb_rects).enumerate() {
+            let rect = graphics::FloatRect::new(top_left.x + rect.left, top_left.y + rect.top, rect.width, rect.height);
+            if hover == Some(*id) {
+                let mut hover_shape = graphics::RectangleShape::from_rect(rect);
+                hover_shape.set_fill_color(Theme::DEFAULT.breadcrumb_hover_color);
+                target.draw(&hover_shape);
+            }
+
+            let mut text = graphics::Text::new(name, &self.view.font, Theme::DEFAULT.breadcrumb_font_size);
+            text.set_fill_color(Theme::DEFAULT.breadcrumb_text_color);
+            text.center();
+            text.set_position(rect.center());
+            target.draw(&text);
+
+            if i != self.view.breadcrumbs.len() - 1 {
+                let mut separator = graphics::Text::new(">", &self.view.font, Theme::DEFAULT.breadcrumb_font_size);
+                separator.set_fill_color(Theme::DEFAULT.breadcrumb_text_color);
+                separator.center();
+                separator.set_position((rect.left + rect.width + Theme::DEFAULT.breadcrumb_separator_width / 2.0, rect.top + rect.height / 2.0));
+                target.draw(&separator);
+            }
+        }
+
+        let fit_button_rect = self.fit_button_rect(top_left);
+        let mut fit_button_shape = graphics::RectangleShape::from_rect(fit_button_rect);
+        fit_button_shape.set_fill_color(if hover == Some(self.view.fit_button_id) { Theme::DEFAULT.button_hover_bg } else { Theme::DEFAULT.button_normal_bg });
+        target.draw(&fit_button_shape);
+        let mut fit_button_text = graphics::Text::new("fit", &self.view.font, Theme::DEFAULT.button_font_size);
+        fit_button_text.set_fill_color(if hover == Some(self.view.fit_button_id) { Theme::DEFAULT.button_hover_fg } else { Theme::DEFAULT.button_normal_fg });
+        fit_button_text.center();
+        fit_button_text.set_position(fit_button_rect.center());
+        target.draw(&fit_button_text);
+
+        if let Some(net) = hovered_net {
+            let drivers = self.view.net_drivers[net];
+            let mut net_text = graphics::Text::new(&format!("{drivers} {} on this net", if drivers == 1 { "driver" } else { "drivers" }), &self.view.font, Theme::DEFAULT.net_info_font_size);
+            let net_rect = graphics::FloatRect::new(
+                top_left.x,
+                top_left.y + self.widget_size.y - Theme::DEFAULT.net_info_height,
+                net_text.local_bounds().width + Theme::DEFAULT.net_info_padding * 2.0,
+                Theme::DEFAULT.net_info_height,
+            );
+            let mut net_shape = graphics::RectangleShape::from_rect(net_rect);
+            net_shape.set_fill_color(Theme::DEFAULT.net_info_bg_color);
+            target.draw(&net_shape);
+            net_text.set_fill_color(if drivers > 1 { Theme::DEFAULT.net_info_conflict_color } else { Theme::DEFAULT.net_info_text_color });
+            net_text.center();
+            net_text.set_position(net_rect.center());
+            target.draw(&net_text);
+        }
     }
 
     fn find_hover(&self, top_left: graphics::Vector2f, mouse: graphics::Vector2f) -> Option<ViewId> {
+        self.top_left.set(top_left);
+
+        let widget_rect = graphics::FloatRect::from_vecs(top_left, self.widget_size);
+        if !widget_rect.contains(mouse) {
+            return None;
+        }
+        if self.fit_button_rect(top_left).contains(mouse) {
+            return Some(self.view.fit_button_id);
+        }
+        if mouse.y < top_left.y + Theme::DEFAULT.breadcrumb_bar_height {
+            for ((id, _), rect) in self.view.breadcrumbs.iter().zip(&self.breadcrumb_rects) {
+                if graphics::FloatRect::new(top_left.x + rect.left, top_left.y + rect.top, rect.width, rect.height).contains(mouse) {
+                    return Some(*id);
+                }
+            }
+            return Some(self.view.id);
+        }
+
+        // everything inside of the widget is in circuit coordinates
+        let mouse = self.camera.screen_to_circuit(widget_rect, mouse);
         // reverse to go in z order from highest to lowest
         for node in self.nodes.iter().rev() {
             if let hover @ Some(_) = node.find_hover(top_left, mouse) {
@@ -324,11 +824,8 @@ impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Da
                 return hover;
             }
         }
-        if graphics::FloatRect::from_vecs(top_left, self.widget_size).contains(mouse) {
-            return Some(self.view.id);
-        }
 
-        None
+        Some(self.view.id)
     }
 
     fn size(&self) -> graphics::Vector2f {
@@ -339,6 +836,26 @@ impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Da
         if target == self.view.id {
             self.targeted_event(app, data, event);
         }
+        if target == self.view.fit_button_id {
+            if let TargetedEvent::LeftMouseDown(_) = event {
+                self.view.state_lens.with_mut(data, |state| state.camera = None);
+            }
+        }
+        if let Some(depth) = self.view.breadcrumbs.iter().position(|(id, _)| *id == target) {
+            // the first breadcrumb is the toplevel, so the depth is how many circuits are left on the stack
+            if let TargetedEvent::LeftMouseDown(_) = event {
+                self.view.state_lens.with_mut(data, |state| {
+                    if depth != state.view_stack.len() {
+                        state.view_stack.truncate(depth);
+                        state.camera = None;
+                        state.selected_gates.clear();
+                    }
+                });
+            }
+        }
+        if self.contains_view(target) {
+            self.widget_event(app, data, event);
+        }
         for node in &self.nodes {
             node.send_targeted_event(app, data, target, event);
         }
@@ -352,15 +869,91 @@ impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Da
 
     fn targeted_event(&self, _: &crate::App, data: &mut Data, event: TargetedEvent) {
         match event {
-            TargetedEvent::LeftMouseDown(_) => {}
-
-            TargetedEvent::RightMouseDown(_) => {
-                // TODO: find a better event for this (probably keyboard shortcut)
-                self.view.state_lens.with_mut(data, |state| state.view_stack.pop());
+            TargetedEvent::LeftMouseDown(mouse_pos) => {
+                // dragging on empty space pans, or selects a box of gates if shift is held down
+                let camera = self.camera;
+                let circuit_mouse_pos = camera.screen_to_circuit(graphics::FloatRect::from_vecs(self.top_left.get(), self.widget_size), mouse_pos);
+                self.view.state_lens.with_mut(data, |state| {
+                    state.camera = Some(camera);
+                    if shift_pressed() {
+                        state.cur_box_select = Some(BoxSelect { start: circuit_mouse_pos, end: circuit_mouse_pos });
+                    } else {
+                        state.cur_pan = Some((mouse_pos, camera.center));
+                        state.selected_gates.clear();
+                    }
+                });
             }
+
+            TargetedEvent::RightMouseDown(_) | TargetedEvent::MouseWheelScrolled(_, _) | TargetedEvent::KeyPressed(_) | TargetedEvent::TextEntered(_) => {}
         }
     }
     fn general_event(&self, app: &crate::App, data: &mut Data, event: GeneralEvent) {
+        match event {
+            GeneralEvent::MouseMoved(mouse_pos) => {
+                let zoom = self.camera.zoom;
+                let circuit_mouse_pos = self.camera.screen_to_circuit(graphics::FloatRect::from_vecs(self.top_left.get(), self.widget_size), mouse_pos);
+                self.view.state_lens.with_mut(data, |state| {
+                    if let (Some((mouse_start, center_start)), Some(camera)) = (state.cur_pan, &mut state.camera) {
+                        camera.center = center_start - (mouse_pos - mouse_start) / zoom;
+                    }
+                    if let Some(wire_drag) = &mut state.cur_wire_drag {
+                        wire_drag.mouse_pos = circuit_mouse_pos;
+                    }
+                    if let Some(box_select) = &mut state.cur_box_select {
+                        box_select.end = circuit_mouse_pos;
+                    }
+                    state.mouse_pos = Some(mouse_pos);
+                });
+                if let Some(gate_drag) = self.view.state_lens.with(data, |state| state.cur_gate_drag.clone()) {
+                    let mut mouse_diff = (mouse_pos - gate_drag.mouse_start) / zoom;
+                    // holding alt places the gates exactly where the mouse is
+                    if let Some((_, gate_start)) = gate_drag.gates.iter().find(|(gate, _)| *gate == gate_drag.gate).filter(|_| !alt_pressed()) {
+                        let snapped = snap_to_grid((gate_start.0 + mouse_diff.x, gate_start.1 + mouse_diff.y));
+                        mouse_diff = graphics::Vector2f::new(snapped.0 - gate_start.0, snapped.1 - gate_start.1);
+                    }
+                    self.view.simulation_lens.with_mut(data, |simulation| {
+                        for (gate, gate_start) in gate_drag.gates {
+                            simulation.set_gate_location(gate, (gate_start.0 + mouse_diff.x, gate_start.1 + mouse_diff.y));
+                        }
+                    });
+                }
+            }
+            GeneralEvent::LeftMouseUp => {
+                let (wire_drag, gate_drag, box_select) = self.view.state_lens.with_mut(data, |state| {
+                    state.cur_pan = None;
+                    (state.cur_wire_drag.take(), state.cur_gate_drag.take(), state.cur_box_select.take())
+                });
+                if let Some(wire_drag) = wire_drag {
+                    self.finish_wire_drag(data, wire_drag);
+                }
+                if let Some(gate_drag) = gate_drag {
+                    self.finish_gate_drag(data, gate_drag);
+                }
+                if let Some(box_select) = box_select {
+                    let box_rect = box_select.rect();
+                    let in_box: Vec<_> = self
+                        .gates
+                        .iter()
+                        .filter(|gate| {
+                            let gate_rect = gate_rect(gate.view.gate_location, gate.view.direction, gate.view.num_inputs, gate.view.num_outputs);
+                            box_rect.contains(gate_rect.position()) && box_rect.contains(gate_rect.position() + gate_rect.size())
+                        })
+                        .map(|gate| gate.view.gate_key)
+                        .collect();
+                    self.view.state_lens.with_mut(data, |state| {
+                        for gate in in_box {
+                            if !state.selected_gates.contains(&gate) {
+                                state.selected_gates.push(gate);
+                            }
+                        }
+                    });
+                }
+                if let Some((new_gate, mouse_pos)) = self.view.palette_lens.with(data, |palette| palette.drop_target()) {
+                    self.drop_gate(data, new_gate, mouse_pos);
+                }
+            }
+        }
+
         for node in &self.nodes {
             node.general_event(app, data, event);
         }
@@ -373,22 +966,257 @@ impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Da
     }
 }
 
-impl<Data, SimulationLens: Lens<Data, simulation::Simulation>, StateLens: Lens<Data, SimulationWidgetState>> ViewWithoutLayout<Data> for GateView<Data, StateLens, SimulationLens> {
-    type WithLayout<'without_layout> = GateViewLayout<'without_layout, Data, StateLens, SimulationLens>
-    where
-        Self: 'without_layout;
+impl<
+        Data,
+        StateLens: Lens<Data, SimulationWidgetState>,
+        SimulationLens: Lens<Data, Simulation>,
+        ProjectLens: Lens<Data, Project>,
+        WaveformLens: Lens<Data, WaveformState>,
+        PaletteLens: Lens<Data, PaletteState>,
+    > SimulationViewLayout<'_, Data, StateLens, SimulationLens, ProjectLens, WaveformLens, PaletteLens>
+{
+    fn fit_button_rect(&self, top_left: graphics::Vector2f) -> graphics::FloatRect {
+        let (width, height) = Theme::DEFAULT.fit_button_size;
+        graphics::FloatRect::new(top_left.x + self.widget_size.x - width, top_left.y, width, height)
+    }
 
-    fn layout(&self, sc: SizeConstraints) -> Self::WithLayout<'_> {
-        GateViewLayout { view: self, widget_size: sc.max }
+    fn contains_view(&self, id: ViewId) -> bool {
+        id == self.view.id
+            || id == self.view.fit_button_id
+            || self.view.breadcrumbs.iter().any(|(breadcrumb_id, _)| *breadcrumb_id == id)
+            || self.gates.iter().any(|gate| gate.view.id == id)
+            || self.nodes.iter().any(|node| node.view.id == id)
+            || self.connections.iter().any(|connection| connection.view.id == id)
+    }
+
+    // events that do the same thing no matter which part of the widget they are sent to
+    fn widget_event(&self, app: &crate::App, data: &mut Data, event: TargetedEvent) {
+        match event {
+            // keyboard shortcuts go to the last simulation that was clicked on
+            TargetedEvent::LeftMouseDown(_) => app.take_focus(self.view.id),
+            TargetedEvent::MouseWheelScrolled(mouse_pos, delta) => self.zoom(data, mouse_pos, delta),
+            TargetedEvent::KeyPressed(key) if key == LEAVE_SUBCIRCUIT_SHORTCUT => self.view.state_lens.with_mut(data, |state| {
+                if state.view_stack.pop().is_some() {
+                    state.camera = None;
+                    state.selected_gates.clear();
+                }
+            }),
+            TargetedEvent::KeyPressed(key) if key == FIT_SHORTCUT => self.view.state_lens.with_mut(data, |state| state.camera = None),
+            TargetedEvent::KeyPressed(key) if key == REMOVE_GATE_SHORTCUT => self.remove_selected_gates(data),
+            TargetedEvent::KeyPressed(key) if key == COPY_SHORTCUT => self.copy_selected_gates(data),
+            TargetedEvent::KeyPressed(key) if key == PASTE_SHORTCUT => self.paste(data),
+            TargetedEvent::KeyPressed(key) if key == EXTRACT_SHORTCUT => self.extract_selected_gates(data),
+            TargetedEvent::KeyPressed(key) if key == INLINE_SHORTCUT => self.inline_selected_gates(data),
+            TargetedEvent::KeyPressed(key) if key == ROTATE_SHORTCUT => self.rotate_hovered_gate(data, simulation::GateDirection::rotated_clockwise),
+            TargetedEvent::KeyPressed(key) if key == ROTATE_BACK_SHORTCUT => self.rotate_hovered_gate(data, simulation::GateDirection::rotated_counterclockwise),
+            TargetedEvent::KeyPressed(key) if key == ROUTED_WIRES_SHORTCUT => self.view.state_lens.with_mut(data, |state| state.routed_wires = !state.routed_wires),
+            TargetedEvent::KeyPressed(key) if key == UNDO_SHORTCUT => self.edit(data, |project| {
+                project.undo();
+            }),
+            TargetedEvent::KeyPressed(key) if REDO_SHORTCUTS.contains(&key) => self.edit(data, |project| {
+                project.redo();
+            }),
+            TargetedEvent::TextEntered(ZOOM_IN_TEXT) => self.zoom(data, graphics::FloatRect::from_vecs(self.top_left.get(), self.widget_size).center(), 1.0),
+            TargetedEvent::TextEntered(ZOOM_OUT_TEXT) => self.zoom(data, graphics::FloatRect::from_vecs(self.top_left.get(), self.widget_size).center(), -1.0),
+            TargetedEvent::RightMouseDown(_) | TargetedEvent::KeyPressed(_) | TargetedEvent::TextEntered(_) => {}
+        }
+    }
+
+    fn finish_wire_drag(&self, data: &mut Data, wire_drag: WireDrag) {
+        let visible_rect = self.camera.visible_rect(self.widget_size);
+        let to = self
+            .nodes
+            .iter()
+            .find(|node| node.view.key != wire_drag.from && vector_dist(node_pos(visible_rect, node.view.pos), wire_drag.mouse_pos) < Theme::DEFAULT.node_rad + Theme::DEFAULT.node_hover_dist);
+        if let Some(to) = to {
+            self.edit(data, |project| {
+                let (Some(a), Some(b)) = (project.simulation.node_path(wire_drag.from), project.simulation.node_path(to.view.key)) else { return };
+                project.run(Command::Connect { a, b });
+            });
+        }
+    }
+
+    fn remove_selected_gates(&self, data: &mut Data) {
+        let selected_gates = self.view.state_lens.with(data, |state| state.selected_gates.clone());
+        // removing a gate changes the paths of the gates after it, so they are removed starting from the last one
+        let mut selected_gates: Vec<_> = self.gates.iter().enumerate().filter(|(_, gate)| selected_gates.contains(&gate.view.gate_key)).map(|(index, gate)| (index, gate.view.gate_key)).collect();
+        selected_gates.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+        self.edit(data, |project| {
+            let commands = selected_gates.into_iter().filter_map(|(_, gate)| Some(Command::RemoveGate { gate: project.simulation.gate_path(gate)? })).collect();
+            project.run(Command::Batch(commands));
+        });
+    }
+
+    fn copy_selected_gates(&self, data: &mut Data) {
+        let selected_gates = self.view.state_lens.with(data, |state| state.selected_gates.clone());
+        // in the same order as in the circuit so that the copies are too
+        let selected_gates: Vec<_> = self.gates.iter().map(|gate| gate.view.gate_key).filter(|gate| selected_gates.contains(gate)).collect();
+        if !selected_gates.is_empty() {
+            self.view.project_lens.with_mut(data, |project| project.clipboard = Some(project.simulation.copy_gates(&selected_gates)));
+        }
+    }
+
+    // replaces the selected gates with a new subcircuit that has them inside of it, which is then selected
+    fn extract_selected_gates(&self, data: &mut Data) {
+        let selected_gates = self.view.state_lens.with(data, |state| state.selected_gates.clone());
+        if selected_gates.is_empty() {
+            return;
+        }
+        let mut extracted = None;
+        self.edit(data, |project| {
+            let gates = selected_gates.iter().filter_map(|gate| project.simulation.gate_path(*gate)).collect();
+            if project.run(Command::ExtractGates { gates }) {
+                // the new subcircuit is after everything else in the circuit being shown
+                extracted = match self.view.current_view {
+                    Some(ck) => project.simulation.circuits()[ck].gates.iter().last().copied(),
+                    None => project.simulation.toplevel_gates().iter().last().copied(),
+                };
+            }
+        });
+        let camera = self.camera;
+        self.view.state_lens.with_mut(data, |state| {
+            state.camera = Some(camera);
+            state.selected_gates = extracted.into_iter().collect();
+        });
+    }
+
+    // replaces every selected subcircuit with the gates inside of it, which are then selected along with the other gates that were selected
+    fn inline_selected_gates(&self, data: &mut Data) {
+        let selected_gates = self.view.state_lens.with(data, |state| state.selected_gates.clone());
+        // like removing, inlining a gate changes the paths of the gates after it
+        let mut selected_gates: Vec<_> = self.gates.iter().enumerate().filter(|(_, gate)| selected_gates.contains(&gate.view.gate_key)).map(|(index, gate)| (index, gate.view.gate_key)).collect();
+        selected_gates.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+        let mut inlined = Vec::new();
+        self.edit(data, |project| {
+            let simulation = &project.simulation;
+            let commands = selected_gates
+                .into_iter()
+                .filter_map(|(_, gate)| {
+                    let Gate::Custom(ck) = simulation.gates()[gate] else { return None };
+                    // the gates inside keep their keys
+                    inlined.extend(simulation.circuits()[ck].gates.iter().copied());
+                    Some(Command::InlineGate { gate: simulation.gate_path(gate)? })
+                })
+                .collect();
+            project.run(Command::Batch(commands));
+        });
+        let camera = self.camera;
+        self.view.state_lens.with_mut(data, |state| {
+            state.camera = Some(camera);
+            state.selected_gates.extend(inlined);
+        });
+    }
+
+    // turns the gate under the mouse, which keeps its center where it is
+    fn rotate_hovered_gate(&self, data: &mut Data, rotate: fn(simulation::GateDirection) -> simulation::GateDirection) {
+        let widget_rect = graphics::FloatRect::from_vecs(self.top_left.get(), self.widget_size);
+        let Some(mouse_pos) = self.view.state_lens.with(data, |state| state.mouse_pos).filter(|mouse_pos| widget_rect.contains(*mouse_pos)) else { return };
+        let mouse_pos = self.camera.screen_to_circuit(widget_rect, mouse_pos);
+        let Some(gate) = self.gates.iter().rev().find(|gate| gate.find_hover(self.top_left.get(), mouse_pos).is_some()) else { return };
+
+        self.edit(data, |project| {
+            let Some(path) = project.simulation.gate_path(gate.view.gate_key) else { return };
+            project.run(Command::SetDirection { gate: path, direction: rotate(gate.view.direction), recorded: true });
+        });
+        // the camera is pinned so that the gate changing shape does not make it fit the circuit again
+        let camera = self.camera;
+        self.view.state_lens.with_mut(data, |state| state.camera = Some(camera));
+    }
+
+    // pastes the copied gates into the circuit being shown at the mouse, or in the middle of the widget if the mouse is somewhere else
+    fn paste(&self, data: &mut Data) {
+        let widget_rect = graphics::FloatRect::from_vecs(self.top_left.get(), self.widget_size);
+        let mouse_pos = self.view.state_lens.with(data, |state| state.mouse_pos).filter(|mouse_pos| widget_rect.contains(*mouse_pos)).unwrap_or(widget_rect.center());
+        let location = self.camera.screen_to_circuit(widget_rect, mouse_pos);
+
+        let mut pasted = Vec::new();
+        self.edit(data, |project| {
+            let Some(copied) = project.clipboard.clone() else { return };
+            let children = |simulation: &Simulation| match self.view.current_view {
+                Some(ck) => simulation.circuits()[ck].gates.iter().copied().collect::<Vec<_>>(),
+                None => simulation.toplevel_gates().iter().copied().collect(),
+            };
+            let num_before = children(&project.simulation).len();
+            if project.run(Command::Paste { parent: self.view.current_view, copied, location: (location.x, location.y) }) {
+                pasted = children(&project.simulation).split_off(num_before);
+            }
+        });
+        // the pasted gates are selected so that they can be dragged to where they should go
+        // the camera is pinned so that they do not make it fit the circuit again
+        let camera = self.camera;
+        self.view.state_lens.with_mut(data, |state| {
+            state.camera = Some(camera);
+            if !pasted.is_empty() {
+                state.selected_gates = pasted;
+            }
+        });
+    }
+
+    fn finish_gate_drag(&self, data: &mut Data, gate_drag: GateDrag) {
+        // remember where the gates were moved to so that they stay there when the project is regenerated
+        self.view.project_lens.with_mut(data, |project| {
+            let moves: Vec<_> = gate_drag
+                .gates
+                .into_iter()
+                .filter_map(|(gate, gate_start)| {
+                    let simulation = &project.simulation;
+                    let location = Gate::location(simulation.circuits(), simulation.gates(), gate);
+                    if (location.x, location.y) == gate_start {
+                        return None;
+                    }
+                    let path = simulation.gate_path(gate)?;
+                    let recorded = project.layout_overlay.contains(&path);
+                    project.layout_overlay.set(path.clone(), (location.x, location.y), Gate::direction(simulation.circuits(), simulation.gates(), gate));
+                    // the gate has already been moved, so this only needs to remember how to move it back
+                    Some(Command::MoveGate { gate: path, location: gate_start, recorded })
+                })
+                .collect();
+            if !moves.is_empty() {
+                project.push_undo(Command::Batch(moves));
+            }
+        });
+    }
+
+    // every change to the structure of the simulation goes through here so that the state never refers to anything that was removed
+    fn edit(&self, data: &mut Data, edit: impl FnOnce(&mut Project)) {
+        self.view.project_lens.with_mut(data, edit);
+        // the state and the simulation cannot be borrowed at the same time
+        let mut state = self.view.state_lens.with(data, |state| state.clone());
+        self.view.simulation_lens.with(data, |simulation| state.forget_removed(simulation));
+        self.view.state_lens.with_mut(data, |old_state| *old_state = state);
+    }
+
+    // adds a gate that was dragged out of the palette where it was let go
+    fn drop_gate(&self, data: &mut Data, new_gate: NewGate, mouse_pos: graphics::Vector2f) {
+        let widget_rect = graphics::FloatRect::from_vecs(self.top_left.get(), self.widget_size);
+        if !widget_rect.contains(mouse_pos) || mouse_pos.y < widget_rect.top + Theme::DEFAULT.breadcrumb_bar_height {
+            return;
+        }
+
+        let pos = self.camera.screen_to_circuit(widget_rect, mouse_pos);
+        let pos = if alt_pressed() { (pos.x, pos.y) } else { snap_to_grid((pos.x, pos.y)) };
+        // the subcircuit that was being dragged could have been removed since the drag started, in which case this does nothing
+        self.edit(data, |project| {
+            project.run(Command::AddGate { parent: self.view.current_view, new_gate, location: pos, direction: simulation::GateDirection::LTR });
+        });
+        // the camera is pinned so that the new gate does not make it fit the circuit again
+        let camera = self.camera;
+        self.view.state_lens.with_mut(data, |state| state.camera = Some(camera));
+    }
+
+    fn zoom(&self, data: &mut Data, mouse_pos: graphics::Vector2f, delta: f32) {
+        let widget_rect = graphics::FloatRect::from_vecs(self.top_left.get(), self.widget_size);
+        let mut camera = self.camera;
+        camera.zoom_around(camera.screen_to_circuit(widget_rect, mouse_pos), Theme::DEFAULT.camera_zoom_speed.powf(delta));
+        self.view.state_lens.with_mut(data, |state| state.camera = Some(camera));
     }
 }
 impl<Data, SimulationLens: Lens<Data, simulation::Simulation>, StateLens: Lens<Data, SimulationWidgetState>> View<Data> for GateViewLayout<'_, Data, StateLens, SimulationLens> {
     fn draw(&self, app: &crate::App, target: &mut dyn graphics::RenderTarget, widget_top_left: graphics::Vector2f, hover: Option<ViewId>) {
         self.draw_inner(app, target, widget_top_left, hover);
     }
-    fn draw_inner(&self, _: &crate::App, target: &mut dyn graphics::RenderTarget, widget_top_left: graphics::Vector2f, hover: Option<ViewId>) {
-        let widget_rect = graphics::FloatRect::from_vecs(widget_top_left, self.widget_size);
-        let gate_rect = gate_rect(widget_rect, self.view.gate_location, self.view.direction, self.view.num_inputs, self.view.num_outputs);
+    fn draw_inner(&self, _: &crate::App, target: &mut dyn graphics::RenderTarget, _: graphics::Vector2f, hover: Option<ViewId>) {
+        let gate_rect = gate_rect(self.view.gate_location, self.view.direction, self.view.num_inputs, self.view.num_outputs);
 
         if Some(self.view.id) == hover {
             // expand by hover distance, this is the "stroke weight"
@@ -406,6 +1234,10 @@ impl<Data, SimulationLens: Lens<Data, simulation::Simulation>, StateLens: Lens<D
 
         let mut gate_shape = graphics::RectangleShape::from_rect(gate_rect);
         gate_shape.set_fill_color(Theme::DEFAULT.gate_color);
+        if self.view.selected {
+            gate_shape.set_outline_color(Theme::DEFAULT.gate_selected_color);
+            gate_shape.set_outline_thickness(Theme::DEFAULT.gate_selected_width);
+        }
         target.draw(&gate_shape);
 
         match self.view.kind {
@@ -428,9 +1260,8 @@ impl<Data, SimulationLens: Lens<Data, simulation::Simulation>, StateLens: Lens<D
         }
     }
 
-    fn find_hover(&self, widget_top_left: graphics::Vector2f, mouse_pos: graphics::Vector2f) -> Option<ViewId> {
-        let widget_rect = graphics::FloatRect::from_vecs(widget_top_left, self.widget_size);
-        let rect = gate_rect(widget_rect, self.view.gate_location, self.view.direction, self.view.num_inputs, self.view.num_outputs);
+    fn find_hover(&self, _: graphics::Vector2f, mouse_pos: graphics::Vector2f) -> Option<ViewId> {
+        let rect = gate_rect(self.view.gate_location, self.view.direction, self.view.num_inputs, self.view.num_outputs);
         if rect.contains(mouse_pos) {
             // TODO: hover distance
             return Some(self.view.id);
@@ -453,29 +1284,46 @@ impl<Data, SimulationLens: Lens<Data, simulation::Simulation>, StateLens: Lens<D
     fn targeted_event(&self, _: &crate::App, data: &mut Data, event: TargetedEvent) {
         match event {
             TargetedEvent::LeftMouseDown(mouse_pos) => {
-                // TODO: remove dragging
-                let cur_gate_pos = self.view.simulation_lens.with(data, |simulation| {
-                    let location = Gate::location(&simulation.circuits, &simulation.gates, self.view.gate_key);
-                    (location.x, location.y)
+                // shift clicking adds or removes the gate from the selection, and clicking without shift drags the selection, which is only this gate if it was not already selected
+                let gate = self.view.gate_key;
+                let selected_gates = self.view.state_lens.with_mut(data, |state| {
+                    if shift_pressed() {
+                        match state.selected_gates.iter().position(|selected| *selected == gate) {
+                            Some(index) => {
+                                state.selected_gates.remove(index);
+                            }
+                            None => state.selected_gates.push(gate),
+                        }
+                        None
+                    } else {
+                        if !state.selected_gates.contains(&gate) {
+                            state.selected_gates = vec![gate];
+                        }
+                        Some(state.selected_gates.clone())
+                    }
                 });
-                self.view.state_lens.with_mut(data, |state| state.cur_gate_drag = Some((self.view.gate_key, mouse_pos, (cur_gate_pos.0, cur_gate_pos.1))));
+                if let Some(selected_gates) = selected_gates {
+                    let gates = self.view.simulation_lens.with(data, |simulation| {
+                        selected_gates
+                            .into_iter()
+                            .map(|gate| {
+                                let location = Gate::location(simulation.circuits(), simulation.gates(), gate);
+                                (gate, (location.x, location.y))
+                            })
+                            .collect()
+                    });
+                    // the camera is pinned so that it does not try to keep fitting the circuit while the gates move
+                    let camera = self.camera;
+                    self.view.state_lens.with_mut(data, |state| {
+                        state.cur_gate_drag = Some(GateDrag { mouse_start: mouse_pos, gate, gates });
+                        state.camera = Some(camera);
+                    });
+                }
 
                 match self.view.kind {
                     GateViewKind::Button(_) => {
                         // TODO: put this into a separate view
-                        self.view.simulation_lens.with_mut(data, |simulation| {
-                            if let Gate::Button { logic, location: _, direction: _ } = &simulation.gates[self.view.gate_key] {
-                                let current_value = logic::get_node_production(&simulation.nodes, logic.nodes.outputs()[0]);
-                                let inverted = match current_value {
-                                    Some(logic::Value::H) => logic::Value::L,
-                                    Some(logic::Value::L) => logic::Value::H,
-                                    Some(logic::Value::Z) => logic::Value::L,
-                                    Some(logic::Value::X) => logic::Value::L,
-                                    None => logic::Value::L,
-                                };
-                                logic::set_node_production(&mut simulation.nodes, logic.nodes.outputs()[0], inverted);
-                            }
-                        });
+                        self.view.simulation_lens.with_mut(data, |simulation| simulation.toggle_button(self.view.gate_key));
                     }
                     GateViewKind::Normal => {}
                 }
@@ -483,51 +1331,32 @@ impl<Data, SimulationLens: Lens<Data, simulation::Simulation>, StateLens: Lens<D
             TargetedEvent::RightMouseDown(_) => {
                 // TODO: find better event for this (probably make a popup with a button)
                 if let Some(ck_zoom) = self.view.ck_to_zoom {
-                    self.view.state_lens.with_mut(data, |state| state.view_stack.push(ck_zoom));
+                    // the circuits on the stack that were removed have to go first or the new one would be hidden behind them
+                    let depth = self.view.state_lens.with(data, |state| {
+                        self.view.simulation_lens.with(data, |simulation| state.view_stack.iter().take_while(|ck| simulation.circuits().contains_key(**ck)).count())
+                    });
+                    self.view.state_lens.with_mut(data, |state| {
+                        state.view_stack.truncate(depth);
+                        state.view_stack.push(ck_zoom);
+                        state.camera = None;
+                        state.selected_gates.clear();
+                    });
                 }
             }
+            TargetedEvent::MouseWheelScrolled(_, _) | TargetedEvent::KeyPressed(_) | TargetedEvent::TextEntered(_) => {}
         }
     }
 
-    fn general_event(&self, _: &crate::App, data: &mut Data, event: GeneralEvent) {
-        if self.view.being_dragged {
-            match event {
-                GeneralEvent::MouseMoved(mouse_pos) => {
-                    if let Some((gate, mouse_start, gate_start)) = self.view.state_lens.with(data, |state| state.cur_gate_drag) {
-                        // TODO: zooming and panning
-                        self.view.simulation_lens.with_mut(data, |simulation| {
-                            let mouse_diff = mouse_pos - mouse_start;
-                            let loc = simulation::Gate::location_mut(&mut simulation.circuits, &mut simulation.gates, gate);
-                            loc.x = gate_start.0 + mouse_diff.x;
-                            loc.y = gate_start.1 + mouse_diff.y;
-                        });
-                    }
-                }
-                GeneralEvent::LeftMouseUp => self.view.state_lens.with_mut(data, |state| {
-                    state.cur_gate_drag = None;
-                }),
-            }
-        } else {
-            // dont care
-        }
-    }
-}
-impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, simulation::Simulation>> ViewWithoutLayout<Data> for NodeView<Data, StateLens, SimulationLens> {
-    type WithLayout<'without_layout> = NodeViewLayout<'without_layout, Data, StateLens, SimulationLens>
-    where
-        Self: 'without_layout;
-
-    fn layout(&self, sc: SizeConstraints) -> Self::WithLayout<'_> {
-        NodeViewLayout { view: self, widget_size: sc.max }
-    }
+    fn general_event(&self, _: &crate::App, _: &mut Data, _: GeneralEvent) {}
 }
-impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, simulation::Simulation>> View<Data> for NodeViewLayout<'_, Data, StateLens, SimulationLens> {
+impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Data, simulation::Simulation>, WaveformLens: Lens<Data, WaveformState>> View<Data>
+    for NodeViewLayout<'_, Data, StateLens, SimulationLens, WaveformLens>
+{
     fn draw(&self, app: &crate::App, target: &mut dyn graphics::RenderTarget, widget_top_left: graphics::Vector2f, hover: Option<ViewId>) {
         self.draw_inner(app, target, widget_top_left, hover);
     }
-    fn draw_inner(&self, _: &crate::App, target: &mut dyn graphics::RenderTarget, widget_top_left: graphics::Vector2f, hover: Option<ViewId>) {
-        let widget_rect = graphics::FloatRect::from_vecs(widget_top_left, self.widget_size);
-        let pos = node_pos(widget_rect, self.view.pos);
+    fn draw_inner(&self, _: &crate::App, target: &mut dyn graphics::RenderTarget, _: graphics::Vector2f, hover: Option<ViewId>) {
+        let pos = node_pos(self.visible_rect, self.view.pos);
         if Some(self.view.id) == hover {
             let hover_rad = Theme::DEFAULT.node_rad + Theme::DEFAULT.node_hover_dist;
             let mut hover_shape = graphics::CircleShape::new(hover_rad, 30); // TODO: put point count in theme
@@ -542,11 +1371,22 @@ impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Da
         node_shape.set_position(pos);
         node_shape.set_fill_color(self.view.color);
         target.draw(&node_shape);
+
+        if let Some(label) = &self.view.label {
+            // labels go on the inside of the gate so that they do not cover up the connections
+            let inward = node_inward_direction(self.view.pos);
+            let mut text = graphics::Text::new(label, &self.view.font, Theme::DEFAULT.node_label_font_size);
+            text.set_fill_color(Theme::DEFAULT.node_label_color);
+            let bounds = text.local_bounds();
+            // put the origin on the side of the text that faces the node
+            text.set_origin(((1.0 - inward.x) / 2.0 * bounds.width + bounds.left, (1.0 - inward.y) / 2.0 * bounds.height + bounds.top));
+            text.set_position(pos + inward * (Theme::DEFAULT.node_rad + Theme::DEFAULT.node_label_dist));
+            target.draw(&text);
+        }
     }
 
-    fn find_hover(&self, widget_top_left: graphics::Vector2f, mouse_pos: graphics::Vector2f) -> Option<ViewId> {
-        let widget_rect = graphics::FloatRect::from_vecs(widget_top_left, self.widget_size);
-        let pos = node_pos(widget_rect, self.view.pos);
+    fn find_hover(&self, _: graphics::Vector2f, mouse_pos: graphics::Vector2f) -> Option<ViewId> {
+        let pos = node_pos(self.visible_rect, self.view.pos);
         if vector_dist(pos, mouse_pos) < Theme::DEFAULT.node_rad + Theme::DEFAULT.node_hover_dist {
             return Some(self.view.id);
         }
@@ -563,45 +1403,44 @@ impl<Data, StateLens: Lens<Data, SimulationWidgetState>, SimulationLens: Lens<Da
         }
     }
 
-    fn targeted_event(&self, _: &crate::App, _: &mut Data, _: TargetedEvent) {}
-    fn general_event(&self, _: &crate::App, _: &mut Data, _: GeneralEvent) {}
-}
-impl<Data, SimulationLens: Lens<Data, simulation::Simulation>, StateLens: Lens<Data, SimulationWidgetState>> ViewWithoutLayout<Data> for ConnectionView<Data, StateLens, SimulationLens> {
-    type WithLayout<'without_layout> = ConnectionViewLayout<'without_layout, Data, StateLens, SimulationLens>
-    where
-        Self: 'without_layout;
-
-    fn layout(&self, sc: SizeConstraints) -> Self::WithLayout<'_> {
-        ConnectionViewLayout { view: self, widget_size: sc.max }
+    fn targeted_event(&self, _: &crate::App, data: &mut Data, event: TargetedEvent) {
+        match event {
+            TargetedEvent::LeftMouseDown(_) => {
+                let mouse_pos = node_pos(self.visible_rect, self.view.pos);
+                self.view.state_lens.with_mut(data, |state| state.cur_wire_drag = Some(WireDrag { from: self.view.key, mouse_pos }));
+            }
+            TargetedEvent::MouseWheelScrolled(_, _) | TargetedEvent::KeyPressed(_) | TargetedEvent::TextEntered(_) => {}
+            TargetedEvent::RightMouseDown(_) => {
+                // the simulation and the waveform cannot be borrowed at the same time, so which nodes to trace has to be found first
+                let traced = self.view.simulation_lens.with(data, |simulation| TracedNodes::for_node(simulation, self.view.key));
+                self.view.waveform_lens.with_mut(data, |waveform| waveform.toggle(traced));
+            }
+        }
     }
+    fn general_event(&self, _: &crate::App, _: &mut Data, _: GeneralEvent) {}
 }
-impl<Data, SimulationLens: Lens<Data, simulation::Simulation>, StateLens: Lens<Data, SimulationWidgetState>> View<Data> for ConnectionViewLayout<'_, Data, StateLens, SimulationLens> {
+impl<Data, ProjectLens: Lens<Data, Project>> View<Data> for ConnectionViewLayout<'_, Data, ProjectLens> {
     fn draw(&self, app: &crate::App, target: &mut dyn graphics::RenderTarget, widget_top_left: graphics::Vector2f, hover: Option<ViewId>) {
         self.draw_inner(app, target, widget_top_left, hover);
     }
-    fn draw_inner(&self, _: &crate::App, target: &mut dyn graphics::RenderTarget, widget_top_left: graphics::Vector2f, hover: Option<ViewId>) {
-        let widget_rect = graphics::FloatRect::from_vecs(widget_top_left, self.widget_size);
-        let pos1 = node_pos(widget_rect, self.view.pos1);
-        let pos2 = node_pos(widget_rect, self.view.pos2);
+    fn draw_inner(&self, _: &crate::App, target: &mut dyn graphics::RenderTarget, _: graphics::Vector2f, hover: Option<ViewId>) {
         let line_weight = if Some(self.view.id) == hover { Theme::DEFAULT.connection_width + Theme::DEFAULT.connection_hover_dist } else { Theme::DEFAULT.connection_width };
 
-        let dist = vector_dist(pos1, pos2);
-        let mut connection_shape = graphics::RectangleShape::new();
-
-        connection_shape.set_size((dist, line_weight));
-        connection_shape.set_origin((0.0, line_weight / 2.0));
-        connection_shape.set_position(pos1);
-        connection_shape.set_rotation(f32::atan2(pos2.y - pos1.y, pos2.x - pos1.x).to_degrees());
-        connection_shape.set_fill_color(self.view.color);
-
-        target.draw(&connection_shape);
+        for segment in self.points.windows(2) {
+            draw_line(target, segment[0], segment[1], line_weight, self.view.color);
+        }
+        // fills in the gaps on the outside of the corners
+        for corner in self.points.iter().skip(1).take(self.points.len().saturating_sub(2)) {
+            let mut corner_shape = graphics::CircleShape::new(line_weight / 2.0, 8);
+            corner_shape.set_origin((line_weight / 2.0, line_weight / 2.0));
+            corner_shape.set_position(*corner);
+            corner_shape.set_fill_color(self.view.color);
+            target.draw(&corner_shape);
+        }
     }
 
-    fn find_hover(&self, widget_top_left: graphics::Vector2f, mouse_pos: graphics::Vector2f) -> Option<ViewId> {
-        let widget_rect = graphics::FloatRect::from_vecs(widget_top_left, self.widget_size);
-        let pos1 = node_pos(widget_rect, self.view.pos1);
-        let pos2 = node_pos(widget_rect, self.view.pos2);
-        if min_dist_to_line_squared((pos1, pos2), mouse_pos) < Theme::DEFAULT.connection_hover_dist.powf(2.0) {
+    fn find_hover(&self, _: graphics::Vector2f, mouse_pos: graphics::Vector2f) -> Option<ViewId> {
+        if self.points.windows(2).any(|segment| min_dist_to_line_squared((segment[0], segment[1]), mouse_pos) < Theme::DEFAULT.connection_hover_dist.powf(2.0)) {
             Some(self.view.id)
         } else {
             None
@@ -618,20 +1457,83 @@ impl<Data, SimulationLens: Lens<Data, simulation::Simulation>, StateLens: Lens<D
         }
     }
 
-    fn targeted_event(&self, _: &crate::App, _: &mut Data, _: TargetedEvent) {}
+    fn targeted_event(&self, _: &crate::App, data: &mut Data, event: TargetedEvent) {
+        match event {
+            TargetedEvent::RightMouseDown(_) => self.view.project_lens.with_mut(data, |project| {
+                let (Some(a), Some(b)) = (project.simulation.node_path(self.view.node1), project.simulation.node_path(self.view.node2)) else { return };
+                project.run(Command::Disconnect { a, b });
+            }),
+            TargetedEvent::LeftMouseDown(_) | TargetedEvent::MouseWheelScrolled(_, _) | TargetedEvent::KeyPressed(_) | TargetedEvent::TextEntered(_) => {}
+        }
+    }
     fn general_event(&self, _: &crate::App, _: &mut Data, _: GeneralEvent) {}
 }
 
-fn gate_rect(widget_rect: graphics::FloatRect, gate_pos: (f32, f32), direction: simulation::GateDirection, num_inputs: usize, num_outputs: usize) -> graphics::FloatRect {
+fn shift_pressed() -> bool {
+    sfml::window::Key::LShift.is_pressed() || sfml::window::Key::RShift.is_pressed()
+}
+fn alt_pressed() -> bool {
+    sfml::window::Key::LAlt.is_pressed() || sfml::window::Key::RAlt.is_pressed()
+}
+
+fn snap_to_grid((x, y): (f32, f32)) -> (f32, f32) {
+    let spacing = Theme::DEFAULT.grid_spacing;
+    ((x / spacing).round() * spacing, (y / spacing).round() * spacing)
+}
+
+// the rect of a gate and every x and y that another gate could line up with: its edges, its center, and its nodes
+fn alignment_lines(gate_location: (f32, f32), direction: simulation::GateDirection, num_inputs: usize, num_outputs: usize) -> (graphics::FloatRect, Vec<f32>, Vec<f32>) {
+    let rect = gate_rect(gate_location, direction, num_inputs, num_outputs);
+    let node_positions: Vec<_> = (0..num_inputs)
+        .map(|index| gate_input_pos(gate_location, direction, num_inputs, num_outputs, index))
+        .chain((0..num_outputs).map(|index| gate_output_pos(gate_location, direction, num_inputs, num_outputs, index)))
+        .collect();
+    let xs = [rect.left, rect.center().x, rect.left + rect.width].into_iter().chain(node_positions.iter().map(|pos| pos.x)).collect();
+    let ys = [rect.top, rect.center().y, rect.top + rect.height].into_iter().chain(node_positions.iter().map(|pos| pos.y)).collect();
+    (rect, xs, ys)
+}
+
+// lines across every pair of a dragged gate and another gate that line up, going from one end of the two gates to the other
+fn alignment_guides(dragged: &[(graphics::FloatRect, Vec<f32>, Vec<f32>)], others: &[(graphics::FloatRect, Vec<f32>, Vec<f32>)], tolerance: f32) -> Vec<(graphics::Vector2f, graphics::Vector2f)> {
+    let mut guides = Vec::new();
+    for (dragged_rect, dragged_xs, dragged_ys) in dragged {
+        for (other_rect, other_xs, other_ys) in others {
+            let top = dragged_rect.top.min(other_rect.top);
+            let bottom = (dragged_rect.top + dragged_rect.height).max(other_rect.top + other_rect.height);
+            let left = dragged_rect.left.min(other_rect.left);
+            let right = (dragged_rect.left + dragged_rect.width).max(other_rect.left + other_rect.width);
+            for x in other_xs.iter().filter(|x| dragged_xs.iter().any(|dragged_x| (dragged_x - *x).abs() <= tolerance)) {
+                guides.push((graphics::Vector2f::new(*x, top), graphics::Vector2f::new(*x, bottom)));
+            }
+            for y in other_ys.iter().filter(|y| dragged_ys.iter().any(|dragged_y| (dragged_y - *y).abs() <= tolerance)) {
+                guides.push((graphics::Vector2f::new(left, *y), graphics::Vector2f::new(right, *y)));
+            }
+        }
+    }
+    guides
+}
+
+fn draw_line(target: &mut dyn graphics::RenderTarget, pos1: graphics::Vector2f, pos2: graphics::Vector2f, width: f32, color: graphics::Color) {
+    let mut shape = graphics::RectangleShape::new();
+    shape.set_size((vector_dist(pos1, pos2), width));
+    shape.set_origin((0.0, width / 2.0));
+    shape.set_position(pos1);
+    shape.set_rotation(f32::atan2(pos2.y - pos1.y, pos2.x - pos1.x).to_degrees());
+    shape.set_fill_color(color);
+    target.draw(&shape);
+}
+
+fn gate_rect(gate_pos: (f32, f32), direction: simulation::GateDirection, num_inputs: usize, num_outputs: usize) -> graphics::FloatRect {
     let gate_size = gate_display_size(direction, num_inputs, num_outputs);
-    graphics::FloatRect::from_vecs(widget_rect.center() + gate_pos.into() - gate_size / 2.0, gate_size)
+    graphics::FloatRect::from_vecs(graphics::Vector2f::from(gate_pos) - gate_size / 2.0, gate_size)
 }
 
 fn gate_display_size(direction: simulation::GateDirection, num_inputs: usize, num_outputs: usize) -> graphics::Vector2f {
     const EXTRA_SPACE: f32 = 40.0;
     const FIXED_SIZE: f32 = 50.0;
 
-    let variable_size = (std::cmp::max(num_inputs, num_outputs) - 1) as f32 * NODE_SPACING + EXTRA_SPACE;
+    // gates with no nodes at all (like a group of gates with no connections going out of it that was made into a subcircuit) are as big as ones with one node
+    let variable_size = (std::cmp::max(num_inputs, num_outputs).max(1) - 1) as f32 * NODE_SPACING + EXTRA_SPACE;
     match direction {
         // nodes on left and right - height variable, width constant
         simulation::GateDirection::LTR | simulation::GateDirection::RTL => graphics::Vector2f::new(FIXED_SIZE, variable_size),
@@ -647,15 +1549,16 @@ fn coord_centered_around(center: f32, total: usize, index: usize) -> f32 {
     box_start_y + (index as f32) * NODE_SPACING
 }
 
-fn circuit_input_pos(widget_rect: graphics::FloatRect, num_inputs: usize, num_outputs: usize, index: usize) -> graphics::Vector2f {
-    graphics::Vector2f::new(widget_rect.left, coord_centered_around(widget_rect.center().y, num_inputs, index))
+// the inputs and outputs of the circuit being viewed stay on the edges of the widget
+fn circuit_input_pos(visible_rect: graphics::FloatRect, num_inputs: usize, num_outputs: usize, index: usize) -> graphics::Vector2f {
+    graphics::Vector2f::new(visible_rect.left, coord_centered_around(visible_rect.center().y, num_inputs, index))
 }
-fn circuit_output_pos(widget_rect: graphics::FloatRect, num_inputs: usize, num_outputs: usize, index: usize) -> graphics::Vector2f {
-    graphics::Vector2f::new(widget_rect.left + widget_rect.width, coord_centered_around(widget_rect.center().y, num_outputs, index))
+fn circuit_output_pos(visible_rect: graphics::FloatRect, num_inputs: usize, num_outputs: usize, index: usize) -> graphics::Vector2f {
+    graphics::Vector2f::new(visible_rect.left + visible_rect.width, coord_centered_around(visible_rect.center().y, num_outputs, index))
 }
 
-fn gate_input_pos(widget_rect: graphics::FloatRect, gate_location: (f32, f32), direction: simulation::GateDirection, num_inputs: usize, num_outputs: usize, idx: usize) -> graphics::Vector2f {
-    let rect = gate_rect(widget_rect, gate_location, direction, num_inputs, num_outputs);
+fn gate_input_pos(gate_location: (f32, f32), direction: simulation::GateDirection, num_inputs: usize, num_outputs: usize, idx: usize) -> graphics::Vector2f {
+    let rect = gate_rect(gate_location, direction, num_inputs, num_outputs);
 
     let graphics::Vector2 { x: center_x, y: center_y } = rect.center();
     let left_x = rect.left;
@@ -670,8 +1573,8 @@ fn gate_input_pos(widget_rect: graphics::FloatRect, gate_location: (f32, f32), d
         simulation::GateDirection::BTT => graphics::Vector2f::new(coord_centered_around(center_x, num_inputs, idx), bottom_y),
     }
 }
-fn gate_output_pos(widget_rect: graphics::FloatRect, gate_location: (f32, f32), direction: simulation::GateDirection, num_inputs: usize, num_outputs: usize, idx: usize) -> graphics::Vector2f {
-    let rect = gate_rect(widget_rect, gate_location, direction, num_inputs, num_outputs);
+fn gate_output_pos(gate_location: (f32, f32), direction: simulation::GateDirection, num_inputs: usize, num_outputs: usize, idx: usize) -> graphics::Vector2f {
+    let rect = gate_rect(gate_location, direction, num_inputs, num_outputs);
 
     let graphics::Vector2 { x: center_x, y: center_y } = rect.center();
     let left_x = rect.left;
@@ -687,12 +1590,31 @@ fn gate_output_pos(widget_rect: graphics::FloatRect, gate_location: (f32, f32),
     }
 }
 
-fn node_pos(widget_rect: graphics::FloatRect, pos: NodeViewPos) -> graphics::Vector2f {
+fn node_pos(visible_rect: graphics::FloatRect, pos: NodeViewPos) -> graphics::Vector2f {
+    match pos {
+        NodeViewPos::FarLeftEdge { index, num_inputs, num_outputs } => circuit_input_pos(visible_rect, num_inputs, num_outputs, index),
+        NodeViewPos::FarRightEdge { index, num_inputs, num_outputs } => circuit_output_pos(visible_rect, num_inputs, num_outputs, index),
+        NodeViewPos::GateInput { gate_pos, gate_direction, index, num_inputs, num_outputs } => gate_input_pos(gate_pos, gate_direction, num_inputs, num_outputs, index),
+        NodeViewPos::GateOutput { gate_pos, gate_direction, index, num_inputs, num_outputs } => gate_output_pos(gate_pos, gate_direction, num_inputs, num_outputs, index),
+    }
+}
+
+fn node_inward_direction(pos: NodeViewPos) -> graphics::Vector2f {
     match pos {
-        NodeViewPos::FarLeftEdge { index, num_inputs, num_outputs } => circuit_input_pos(widget_rect, num_inputs, num_outputs, index),
-        NodeViewPos::FarRightEdge { index, num_inputs, num_outputs } => circuit_output_pos(widget_rect, num_inputs, num_outputs, index),
-        NodeViewPos::GateInput { gate_pos, gate_direction, index, num_inputs, num_outputs } => gate_input_pos(widget_rect, gate_pos, gate_direction, num_inputs, num_outputs, index),
-        NodeViewPos::GateOutput { gate_pos, gate_direction, index, num_inputs, num_outputs } => gate_output_pos(widget_rect, gate_pos, gate_direction, num_inputs, num_outputs, index),
+        NodeViewPos::FarLeftEdge { .. } => graphics::Vector2f::new(1.0, 0.0),
+        NodeViewPos::FarRightEdge { .. } => graphics::Vector2f::new(-1.0, 0.0),
+        NodeViewPos::GateInput { gate_direction, .. } => match gate_direction {
+            simulation::GateDirection::LTR => graphics::Vector2f::new(1.0, 0.0),
+            simulation::GateDirection::RTL => graphics::Vector2f::new(-1.0, 0.0),
+            simulation::GateDirection::TTB => graphics::Vector2f::new(0.0, 1.0),
+            simulation::GateDirection::BTT => graphics::Vector2f::new(0.0, -1.0),
+        },
+        NodeViewPos::GateOutput { gate_direction, .. } => match gate_direction {
+            simulation::GateDirection::LTR => graphics::Vector2f::new(-1.0, 0.0),
+            simulation::GateDirection::RTL => graphics::Vector2f::new(1.0, 0.0),
+            simulation::GateDirection::TTB => graphics::Vector2f::new(0.0, -1.0),
+            simulation::GateDirection::BTT => graphics::Vector2f::new(0.0, 1.0),
+        },
     }
 }
 
@@ -0,0 +1,261 @@
+// orthogonal paths for connections that go around gates instead of through them
+//
+// the path is the shortest one on the grid made from the edges of the gates near the connection and the two ends (the hanan grid, which always has the shortest orthogonal path around rectangles)
+// with every turn costing extra so that it does not zigzag
+
+use std::{
+    cell::RefCell,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    rc::Rc,
+};
+
+use logicgates::simulation::NodeKey;
+
+use crate::graphics;
+
+// how far the path goes straight out of a node before it can turn, and how far it stays away from gates
+const NODE_STUB: f32 = 10.0;
+const CLEARANCE: f32 = 6.0;
+// gates this far outside of the box around the two ends are left out, which keeps the grid small
+const SEARCH_MARGIN: f32 = 100.0;
+// connections that go past more gates than this (across a whole big circuit) are not routed because it would take too long, so they go in an L instead
+const MAX_OBSTACLES: usize = 64;
+// in the same units as length
+const TURN_COST: f32 = 30.0;
+
+// routes of the connections shown last time, which are only worked out again when one of the ends or a gate near the connection moves
+// this is shared between the pane state and its layouts, but every pane has its own because panes can show different circuits, so cloning it (when a pane is split) gives an empty one
+pub(super) struct RouteCache(Rc<RefCell<HashMap<(NodeKey, NodeKey), CachedRoute>>>);
+struct CachedRoute {
+    ends: [(graphics::Vector2f, graphics::Vector2f); 2],
+    obstacles: Vec<graphics::FloatRect>,
+    route: Rc<[graphics::Vector2f]>,
+    used: bool,
+}
+
+impl RouteCache {
+    pub(super) fn new() -> RouteCache {
+        RouteCache(Rc::new(RefCell::new(HashMap::new())))
+    }
+
+    // the same cache instead of a new one, for the layouts to put routes into
+    pub(super) fn shared(&self) -> RouteCache {
+        RouteCache(self.0.clone())
+    }
+
+    // ends are (position, direction going out of the node), and gates are the rects of every gate being shown
+    pub(super) fn route(
+        &self,
+        connection: (NodeKey, NodeKey),
+        from: (graphics::Vector2f, graphics::Vector2f),
+        to: (graphics::Vector2f, graphics::Vector2f),
+        gates: &[graphics::FloatRect],
+    ) -> Rc<[graphics::Vector2f]> {
+        let left = from.0.x.min(to.0.x) - SEARCH_MARGIN;
+        let top = from.0.y.min(to.0.y) - SEARCH_MARGIN;
+        let search_rect = graphics::FloatRect::new(left, top, from.0.x.max(to.0.x) + SEARCH_MARGIN - left, from.0.y.max(to.0.y) + SEARCH_MARGIN - top);
+        let obstacles: Vec<_> = gates.iter().filter(|gate| gate.intersection(&search_rect).is_some()).copied().collect();
+
+        let mut routes = self.0.borrow_mut();
+        if let Some(cached) = routes.get_mut(&connection).filter(|cached| cached.ends == [from, to] && cached.obstacles == obstacles) {
+            cached.used = true;
+            return cached.route.clone();
+        }
+        let route: Rc<[_]> = route(from, to, &obstacles).into();
+        routes.insert(connection, CachedRoute { ends: [from, to], obstacles, route: route.clone(), used: true });
+        route
+    }
+
+    // forgets the routes of connections that were not shown since the last time this was called
+    pub(super) fn forget_unused(&self) {
+        self.0.borrow_mut().retain(|_, cached| std::mem::replace(&mut cached.used, false));
+    }
+}
+
+impl Clone for RouteCache {
+    fn clone(&self) -> RouteCache {
+        RouteCache::new()
+    }
+}
+
+// gives every corner of the path including both ends
+fn route(from: (graphics::Vector2f, graphics::Vector2f), to: (graphics::Vector2f, graphics::Vector2f), obstacles: &[graphics::FloatRect]) -> Vec<graphics::Vector2f> {
+    let start = from.0 + from.1 * NODE_STUB;
+    let end = to.0 + to.1 * NODE_STUB;
+    // a node that is inside of another gate (because gates overlap) would not be able to get out, so that gate is ignored
+    let obstacles: Vec<_> = obstacles
+        .iter()
+        .map(|rect| graphics::FloatRect::new(rect.left - CLEARANCE, rect.top - CLEARANCE, rect.width + CLEARANCE * 2.0, rect.height + CLEARANCE * 2.0))
+        .filter(|rect| !strictly_contains(rect, start) && !strictly_contains(rect, end))
+        .collect();
+
+    let corners = if obstacles.len() <= MAX_OBSTACLES { shortest_path(&obstacles, (start, from.1), (end, -to.1)) } else { None };
+    // every way is blocked, so this goes through whatever is in the way
+    let corners = corners.unwrap_or_else(|| vec![start, graphics::Vector2f::new(end.x, start.y), end]);
+    simplify(std::iter::once(from.0).chain(corners).chain(std::iter::once(to.0)).collect())
+}
+
+// the points on the grid where the path turns, going from start in its direction and into end in its direction
+fn shortest_path(
+    obstacles: &[graphics::FloatRect],
+    (start, start_direction): (graphics::Vector2f, graphics::Vector2f),
+    (end, end_direction): (graphics::Vector2f, graphics::Vector2f),
+) -> Option<Vec<graphics::Vector2f>> {
+    let mut xs: Vec<f32> = obstacles.iter().flat_map(|rect| [rect.left, rect.left + rect.width]).chain([start.x, end.x]).collect();
+    let mut ys: Vec<f32> = obstacles.iter().flat_map(|rect| [rect.top, rect.top + rect.height]).chain([start.y, end.y]).collect();
+    for coords in [&mut xs, &mut ys] {
+        coords.sort_by(f32::total_cmp);
+        coords.dedup();
+    }
+    let index_of = |coords: &[f32], value: f32| coords.iter().position(|coord| *coord == value);
+    let start_index = (index_of(&xs, start.x)?, index_of(&ys, start.y)?);
+    let end_index = (index_of(&xs, end.x)?, index_of(&ys, end.y)?);
+    let point = |(x, y): (usize, usize)| graphics::Vector2f::new(xs[x], ys[y]);
+
+    // every edge of an obstacle is a line of the grid, so going between two neighboring points of the grid is either entirely through the inside of an obstacle or not at all
+    // going along the edge of an obstacle is allowed
+    // these are indexed by the point on the left or top end of the step
+    let (width, height) = (xs.len(), ys.len());
+    let mut right_blocked = vec![false; width * height];
+    let mut down_blocked = vec![false; width * height];
+    for rect in obstacles {
+        let (Some(left), Some(right), Some(top), Some(bottom)) = (index_of(&xs, rect.left), index_of(&xs, rect.left + rect.width), index_of(&ys, rect.top), index_of(&ys, rect.top + rect.height))
+        else {
+            continue;
+        };
+        for y in top..=bottom {
+            for x in left..=right {
+                if y != top && y != bottom && x != right {
+                    right_blocked[y * width + x] = true;
+                }
+                if x != left && x != right && y != bottom {
+                    down_blocked[y * width + x] = true;
+                }
+            }
+        }
+    }
+    let blocked = |(x, y): (usize, usize), direction: Direction| match direction {
+        Direction::Right => right_blocked[y * width + x],
+        Direction::Down => down_blocked[y * width + x],
+        Direction::Left => right_blocked[y * width + x - 1],
+        Direction::Up => down_blocked[(y - 1) * width + x],
+    };
+
+    // dijkstra where the state is a point on the grid and the direction that the path was going when it got there
+    // the last part of what is in the queue is if the path already went into the end, which is separate because going into the end from the wrong direction means turning there
+    // costs are kept as integers in hundredths so that they can go in the heap
+    let to_cost = |cost: f32| (cost * 100.0).round() as u64;
+    let mut best: HashMap<((usize, usize), Direction), u64> = HashMap::new();
+    let mut came_from: HashMap<((usize, usize), Direction), ((usize, usize), Direction)> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    let start_state = (start_index, Direction::of(start_direction));
+    best.insert(start_state, 0);
+    queue.push(Reverse((0, start_state, false)));
+
+    while let Some(Reverse((cost, state @ (index, direction), finished))) = queue.pop() {
+        if finished {
+            let mut corners = vec![point(index)];
+            let mut state = state;
+            while let Some(previous) = came_from.get(&state) {
+                corners.push(point(previous.0));
+                state = *previous;
+            }
+            corners.reverse();
+            return Some(corners);
+        }
+        if best.get(&state).is_some_and(|best| *best < cost) {
+            continue;
+        }
+        if index == end_index {
+            let turn = if direction == Direction::of(end_direction) { 0 } else { to_cost(TURN_COST) };
+            queue.push(Reverse((cost + turn, state, true)));
+            continue;
+        }
+
+        for next_direction in [Direction::Right, Direction::Left, Direction::Down, Direction::Up] {
+            // going back the way it came is never shorter
+            if next_direction == direction.opposite() {
+                continue;
+            }
+            let Some(next) = next_direction.step(index, width, height) else { continue };
+            if blocked(index, next_direction) {
+                continue;
+            }
+            let turn = if next_direction == direction { 0.0 } else { TURN_COST };
+            let next_cost = cost + to_cost(super::vector_dist(point(index), point(next)) + turn);
+            let next_state = (next, next_direction);
+            if best.get(&next_state).is_none_or(|best| next_cost < *best) {
+                best.insert(next_state, next_cost);
+                came_from.insert(next_state, state);
+                queue.push(Reverse((next_cost, next_state, false)));
+            }
+        }
+    }
+
+    None
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Direction {
+    Right,
+    Left,
+    Down,
+    Up,
+}
+
+impl Direction {
+    fn of(vector: graphics::Vector2f) -> Direction {
+        if vector.x.abs() >= vector.y.abs() {
+            if vector.x >= 0.0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            }
+        } else if vector.y >= 0.0 {
+            Direction::Down
+        } else {
+            Direction::Up
+        }
+    }
+
+    fn opposite(self) -> Direction {
+        match self {
+            Direction::Right => Direction::Left,
+            Direction::Left => Direction::Right,
+            Direction::Down => Direction::Up,
+            Direction::Up => Direction::Down,
+        }
+    }
+
+    fn step(self, (x, y): (usize, usize), width: usize, height: usize) -> Option<(usize, usize)> {
+        match self {
+            Direction::Right if x + 1 < width => Some((x + 1, y)),
+            Direction::Left => Some((x.checked_sub(1)?, y)),
+            Direction::Down if y + 1 < height => Some((x, y + 1)),
+            Direction::Up => Some((x, y.checked_sub(1)?)),
+            _ => None,
+        }
+    }
+}
+
+fn strictly_contains(rect: &graphics::FloatRect, point: graphics::Vector2f) -> bool {
+    rect.left < point.x && point.x < rect.left + rect.width && rect.top < point.y && point.y < rect.top + rect.height
+}
+
+// removes points in the middle of straight lines and points that are the same as the one before
+fn simplify(points: Vec<graphics::Vector2f>) -> Vec<graphics::Vector2f> {
+    let mut simplified: Vec<graphics::Vector2f> = Vec::new();
+    for point in points {
+        if simplified.last() == Some(&point) {
+            continue;
+        }
+        if let [.., before, last] = simplified[..] {
+            if (before.x == last.x && last.x == point.x) || (before.y == last.y && last.y == point.y) {
+                simplified.pop();
+            }
+        }
+        simplified.push(point);
+    }
+    simplified
+}
@@ -133,7 +133,7 @@ impl<Data, Value: Display + Copy + Add<Value, Output = Value> + Ord, StateLens:
                 let cur_value = self.slider.value_lens.with(data, |value| *value);
                 self.slider.state_lens.with_mut(data, |state| state.drag_start = Some((mouse_pos, cur_value)));
             }
-            TargetedEvent::RightMouseDown(_) => {}
+            TargetedEvent::RightMouseDown(_) | TargetedEvent::MouseWheelScrolled(_, _) | TargetedEvent::KeyPressed(_) | TargetedEvent::TextEntered(_) => {}
         }
     }
     fn general_event(&self, _: &crate::App, data: &mut Data, event: GeneralEvent) {
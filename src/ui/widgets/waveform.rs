@@ -0,0 +1,278 @@
+use std::{collections::VecDeque, marker::PhantomData, rc::Rc};
+
+use logicgates::simulation::{hierarchy, logic, Gate, NodeKey, Simulation};
+use sfml::graphics::{Shape, Transformable};
+
+use crate::{
+    graphics::{self, CenterText},
+    theme::Theme,
+    view::{
+        id::{ViewId, ViewIdMaker},
+        lens::Lens,
+        GeneralEvent, SizeConstraints, TargetedEvent, View, ViewWithoutLayout,
+    },
+};
+
+// timing diagram of the nodes that are being traced, newest tick on the right
+// nodes are traced by right clicking on them in the simulation widget, and a trace is removed by right clicking on it here
+
+const HISTORY_LEN: usize = 1000;
+
+pub(crate) struct WaveformState {
+    traces: Vec<Trace>,
+}
+struct Trace {
+    name: String,
+    // lowest bit first
+    nodes: Vec<NodeKey>,
+    history: VecDeque<Vec<logic::Value>>,
+}
+
+impl WaveformState {
+    pub(crate) fn new() -> WaveformState {
+        WaveformState { traces: Vec::new() }
+    }
+
+    pub(crate) fn record(&mut self, simulation: &Simulation) {
+        // nodes that were removed from the simulation take their traces with them
+        self.traces.retain(|trace| trace.nodes.iter().all(|node| simulation.nodes().contains_key(*node)));
+        for trace in &mut self.traces {
+            if trace.history.len() == HISTORY_LEN {
+                trace.history.pop_front();
+            }
+            trace.history.push_back(trace.nodes.iter().map(|node| simulation.node_value(*node)).collect());
+        }
+    }
+
+    // starts tracing the nodes, or stops if they are already being traced
+    pub(crate) fn toggle(&mut self, nodes: TracedNodes) {
+        if let Some(index) = self.traces.iter().position(|trace| trace.nodes.iter().any(|node| nodes.nodes.contains(node))) {
+            self.traces.remove(index);
+        } else {
+            self.traces.push(Trace { name: nodes.name, nodes: nodes.nodes, history: VecDeque::new() });
+        }
+    }
+}
+
+pub(crate) struct TracedNodes {
+    name: String,
+    nodes: Vec<NodeKey>,
+}
+
+impl TracedNodes {
+    // nodes that are part of a bundle bring the rest of the bundle along with them so that it can be shown as one number
+    pub(crate) fn for_node(simulation: &Simulation, node: NodeKey) -> TracedNodes {
+        let (circuits, gates) = (simulation.circuits(), simulation.gates());
+        let (owner_name, which, index, siblings, names) = match simulation.nodes()[node].parent.kind() {
+            hierarchy::NodeParentKind::GateIn(gk, i) => (gates[gk].name(circuits), "in", i, Gate::inputs(circuits, gates, gk), Gate::input_names(circuits, gates, gk)),
+            hierarchy::NodeParentKind::GateOut(gk, i) => (gates[gk].name(circuits), "out", i, Gate::outputs(circuits, gates, gk), Gate::output_names(circuits, gates, gk)),
+            hierarchy::NodeParentKind::CircuitIn(ck, i) => {
+                let circuit = &circuits[ck];
+                (circuit.name.as_str(), "in", i, &circuit.nodes.inputs()[..], circuit.nodes.input_names())
+            }
+            hierarchy::NodeParentKind::CircuitOut(ck, i) => {
+                let circuit = &circuits[ck];
+                (circuit.name.as_str(), "out", i, &circuit.nodes.outputs()[..], circuit.nodes.output_names())
+            }
+        };
+
+        match names.bundles().iter().find(|bundle| (bundle.start..bundle.start + bundle.size).contains(&index)) {
+            Some(bundle) => TracedNodes { name: format!("{owner_name}.{}", bundle.name), nodes: siblings[bundle.start..bundle.start + bundle.size].to_vec() },
+            None => match names.name(index) {
+                Some(node_name) => TracedNodes { name: format!("{owner_name}.{node_name}"), nodes: vec![node] },
+                None => TracedNodes { name: format!("{owner_name}.{which}[{index}]"), nodes: vec![node] },
+            },
+        }
+    }
+}
+
+struct WaveformView<Data, WaveformLens: Lens<Data, WaveformState>> {
+    id: ViewId,
+    waveform_lens: WaveformLens,
+    rows: Vec<RowView>,
+    font: Rc<sfml::SfBox<graphics::Font>>,
+
+    _phantom: PhantomData<fn(&Data)>,
+}
+struct RowView {
+    id: ViewId,
+    name: String,
+    samples: Vec<Sample>,
+}
+#[derive(Copy, Clone, PartialEq)]
+enum Sample {
+    Bit(logic::Value),
+    // bundles are shown as one number, unless some of the bits are not high or low
+    Number { value: u64, num_bits: usize },
+    HighImpedance,
+    Error,
+}
+struct WaveformViewLayout<'original, Data, WaveformLens: Lens<Data, WaveformState>> {
+    view: &'original WaveformView<Data, WaveformLens>,
+    size: graphics::Vector2f,
+}
+
+pub(crate) fn waveform<Data>(id_maker: &mut ViewIdMaker, waveform_lens: impl Lens<Data, WaveformState>, font: &Rc<sfml::SfBox<graphics::Font>>, data: &Data) -> impl ViewWithoutLayout<Data> {
+    let rows = waveform_lens.with(data, |waveform| {
+        waveform.traces.iter().map(|trace| RowView { id: id_maker.next_id(), name: trace.name.clone(), samples: trace.history.iter().map(|values| sample(values)).collect() }).collect()
+    });
+    WaveformView { id: id_maker.next_id(), waveform_lens, rows, font: font.clone(), _phantom: PhantomData }
+}
+
+fn sample(values: &[logic::Value]) -> Sample {
+    match values {
+        [value] => Sample::Bit(*value),
+        _ if values.iter().all(|value| *value == logic::Value::Z) => Sample::HighImpedance,
+        _ => values.iter().take(u64::BITS as usize).enumerate().try_fold(0, |acc, (i, value)| match value {
+            logic::Value::H => Some(acc | (1 << i)),
+            logic::Value::L => Some(acc),
+            logic::Value::Z | logic::Value::X => None,
+        })
+        .map_or(Sample::Error, |value| Sample::Number { value, num_bits: values.len() }),
+    }
+}
+
+impl<Data, WaveformLens: Lens<Data, WaveformState>> ViewWithoutLayout<Data> for WaveformView<Data, WaveformLens> {
+    type WithLayout<'without_layout> = WaveformViewLayout<'without_layout, Data, WaveformLens> where Self: 'without_layout;
+
+    fn layout(&self, sc: SizeConstraints) -> Self::WithLayout<'_> {
+        WaveformViewLayout { view: self, size: sc.max }
+    }
+}
+impl<Data, WaveformLens: Lens<Data, WaveformState>> View<Data> for WaveformViewLayout<'_, Data, WaveformLens> {
+    fn draw_inner(&self, _: &crate::App, target: &mut dyn graphics::RenderTarget, top_left: graphics::Vector2f, hover: Option<ViewId>) {
+        let rect = graphics::FloatRect::from_vecs(top_left, self.size);
+        let mut background = graphics::RectangleShape::from_rect(rect);
+        background.set_fill_color(Theme::DEFAULT.waveform_bg_color);
+        target.draw(&background);
+
+        for (row_index, row) in self.view.rows.iter().enumerate() {
+            let row_rect = self.row_rect(top_left, row_index);
+            if Some(row.id) == hover {
+                let mut hover_shape = graphics::RectangleShape::from_rect(row_rect);
+                hover_shape.set_fill_color(Theme::DEFAULT.waveform_hover_color);
+                target.draw(&hover_shape);
+            }
+
+            let mut text = graphics::Text::new(&row.name, &self.view.font, Theme::DEFAULT.waveform_font_size);
+            text.set_fill_color(Theme::DEFAULT.waveform_text_color);
+            text.center_vertically();
+            text.set_position((row_rect.left + Theme::DEFAULT.waveform_padding, row_rect.top + row_rect.height / 2.0));
+            target.draw(&text);
+
+            self.draw_samples(target, row_rect, &row.samples);
+        }
+    }
+
+    fn find_hover(&self, top_left: graphics::Vector2f, mouse: graphics::Vector2f) -> Option<ViewId> {
+        for (row_index, row) in self.view.rows.iter().enumerate() {
+            if self.row_rect(top_left, row_index).contains(mouse) {
+                return Some(row.id);
+            }
+        }
+        if graphics::FloatRect::from_vecs(top_left, self.size).contains(mouse) {
+            return Some(self.view.id);
+        }
+        None
+    }
+
+    fn size(&self) -> graphics::Vector2f {
+        self.size
+    }
+
+    fn send_targeted_event(&self, _: &crate::App, data: &mut Data, target: ViewId, event: TargetedEvent) {
+        // the rows are not separate views so they are handled here
+        if let Some(row_index) = self.view.rows.iter().position(|row| row.id == target) {
+            match event {
+                TargetedEvent::LeftMouseDown(_) | TargetedEvent::MouseWheelScrolled(_, _) | TargetedEvent::KeyPressed(_) | TargetedEvent::TextEntered(_) => {}
+                TargetedEvent::RightMouseDown(_) => {
+                    self.view.waveform_lens.with_mut(data, |waveform| {
+                        if row_index < waveform.traces.len() {
+                            waveform.traces.remove(row_index);
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    fn targeted_event(&self, _: &crate::App, _: &mut Data, _: TargetedEvent) {}
+    fn general_event(&self, _: &crate::App, _: &mut Data, _: GeneralEvent) {}
+}
+
+impl<Data, WaveformLens: Lens<Data, WaveformState>> WaveformViewLayout<'_, Data, WaveformLens> {
+    fn row_rect(&self, top_left: graphics::Vector2f, row_index: usize) -> graphics::FloatRect {
+        graphics::FloatRect::new(top_left.x, top_left.y + row_index as f32 * Theme::DEFAULT.waveform_row_height, self.size.x, Theme::DEFAULT.waveform_row_height)
+    }
+
+    fn draw_samples(&self, target: &mut dyn graphics::RenderTarget, row_rect: graphics::FloatRect, samples: &[Sample]) {
+        let high_y = row_rect.top + Theme::DEFAULT.waveform_padding;
+        let low_y = row_rect.top + row_rect.height - Theme::DEFAULT.waveform_padding;
+        let middle_y = row_rect.top + row_rect.height / 2.0;
+        let trace_left = row_rect.left + Theme::DEFAULT.waveform_label_width;
+        let trace_right = row_rect.left + row_rect.width;
+
+        let sample_width = Theme::DEFAULT.waveform_sample_width;
+        let num_visible = (((trace_right - trace_left) / sample_width).max(0.0) as usize).min(samples.len());
+        let visible = &samples[samples.len() - num_visible..];
+        let sample_x = |i: usize| trace_right - (visible.len() - i) as f32 * sample_width;
+
+        // consecutive samples that are the same are drawn together so that numbers only get written once per change
+        let mut start = 0;
+        while start < visible.len() {
+            let mut end = start + 1;
+            while end < visible.len() && visible[end] == visible[start] {
+                end += 1;
+            }
+            let (left, right) = (sample_x(start), sample_x(end));
+
+            match visible[start] {
+                Sample::Bit(value) => {
+                    let (y, color) = match value {
+                        logic::Value::H => (high_y, Theme::DEFAULT.on_color),
+                        logic::Value::L => (low_y, Theme::DEFAULT.off_color),
+                        logic::Value::Z => (middle_y, Theme::DEFAULT.high_impedance_color),
+                        logic::Value::X => (middle_y, Theme::DEFAULT.err_color),
+                    };
+                    if value == logic::Value::X {
+                        fill_rect(target, graphics::FloatRect::new(left, high_y, right - left, low_y - high_y), Theme::DEFAULT.err_color);
+                    } else {
+                        fill_rect(target, graphics::FloatRect::new(left, y - Theme::DEFAULT.waveform_line_width / 2.0, right - left, Theme::DEFAULT.waveform_line_width), color);
+                    }
+
+                    if start > 0 {
+                        fill_rect(target, graphics::FloatRect::new(left - Theme::DEFAULT.waveform_line_width / 2.0, high_y, Theme::DEFAULT.waveform_line_width, low_y - high_y), Theme::DEFAULT.waveform_transition_color);
+                    }
+                }
+                Sample::Number { value, num_bits } => {
+                    fill_rect(target, graphics::FloatRect::new(left, high_y, right - left, Theme::DEFAULT.waveform_line_width), Theme::DEFAULT.on_color);
+                    fill_rect(target, graphics::FloatRect::new(left, low_y - Theme::DEFAULT.waveform_line_width, right - left, Theme::DEFAULT.waveform_line_width), Theme::DEFAULT.on_color);
+                    fill_rect(target, graphics::FloatRect::new(left, high_y, Theme::DEFAULT.waveform_line_width, low_y - high_y), Theme::DEFAULT.waveform_transition_color);
+
+                    let mut text = graphics::Text::new(&format!("{:0width$x}", value, width = num_bits.div_ceil(4)), &self.view.font, Theme::DEFAULT.waveform_font_size);
+                    // only write the number if it fits
+                    if text.local_bounds().width < right - left - Theme::DEFAULT.waveform_padding {
+                        text.set_fill_color(Theme::DEFAULT.waveform_text_color);
+                        text.center();
+                        text.set_position(((left + right) / 2.0, middle_y));
+                        target.draw(&text);
+                    }
+                }
+                Sample::HighImpedance => {
+                    fill_rect(target, graphics::FloatRect::new(left, middle_y - Theme::DEFAULT.waveform_line_width / 2.0, right - left, Theme::DEFAULT.waveform_line_width), Theme::DEFAULT.high_impedance_color);
+                }
+                Sample::Error => {
+                    fill_rect(target, graphics::FloatRect::new(left, high_y, right - left, low_y - high_y), Theme::DEFAULT.err_color);
+                }
+            }
+
+            start = end;
+        }
+    }
+}
+
+fn fill_rect(target: &mut dyn graphics::RenderTarget, rect: graphics::FloatRect, color: graphics::Color) {
+    let mut shape = graphics::RectangleShape::from_rect(rect);
+    shape.set_fill_color(color);
+    target.draw(&shape);
+}
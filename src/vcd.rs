@@ -0,0 +1,184 @@
+use std::{collections::HashSet, io::Write};
+
+use crate::simulation::{logic, path, Gate, GateKey, NodeKey, Simulation};
+
+// records nodes every tick and writes them as a value change dump, which waveform viewers like gtkwave can open
+// every node becomes a 1 bit wire, in scopes that follow the hierarchy of gates that it is in (see simulation::path)
+// one tick is one unit of time
+
+pub struct VcdRecorder {
+    signals: Vec<Signal>,
+    // the values of every signal at the first tick that was recorded, then only the changes after that
+    initial: Option<(usize, Vec<logic::Value>)>,
+    changes: Vec<(usize, Vec<(usize, logic::Value)>)>,
+    last_values: Vec<logic::Value>,
+}
+
+struct Signal {
+    node: NodeKey,
+    scopes: Vec<String>,
+    name: String,
+}
+
+struct Scope<'s> {
+    name: &'s str,
+    signals: Vec<usize>,
+    children: Vec<Scope<'s>>,
+}
+
+impl VcdRecorder {
+    pub fn new(simulation: &Simulation, nodes: &[NodeKey]) -> VcdRecorder {
+        let mut seen = HashSet::new();
+        let signals = nodes
+            .iter()
+            .filter(|node| seen.insert(**node))
+            .map(|node| {
+                let node_path = simulation.node_path(*node).unwrap_or_else(|| "unknown".to_string());
+                let mut segments: Vec<String> = node_path.split('/').map(str::to_string).collect();
+                let last = segments.pop().unwrap_or_default();
+                // nodes with names are much easier to find by name than by in[3] or out[0]
+                // but an input and an output can have the same name, so the name still says which one it is
+                let name = match simulation.node_name(*node) {
+                    Some(name) => format!("{}.{}", last.split('[').next().unwrap_or_default(), name),
+                    None => last,
+                };
+                Signal { node: *node, scopes: segments, name }
+            })
+            .collect();
+
+        VcdRecorder { signals, initial: None, changes: Vec::new(), last_values: Vec::new() }
+    }
+
+    pub fn record(&mut self, simulation: &Simulation, tick: usize) {
+        let values: Vec<_> = self.signals.iter().map(|signal| simulation.node_value(signal.node)).collect();
+        if self.initial.is_none() {
+            self.initial = Some((tick, values.clone()));
+        } else {
+            let changed: Vec<_> = values.iter().zip(&self.last_values).enumerate().filter(|(_, (new, old))| new != old).map(|(i, (new, _))| (i, *new)).collect();
+            if !changed.is_empty() {
+                self.changes.push((tick, changed));
+            }
+        }
+        self.last_values = values;
+    }
+
+    pub fn write(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "$version logicgates $end")?;
+        writeln!(out, "$timescale 1 ns $end")?;
+
+        let mut toplevel: Vec<Scope> = Vec::new();
+        for (i, signal) in self.signals.iter().enumerate() {
+            let mut scopes = &mut toplevel;
+            let mut signals = None;
+            for scope_name in &signal.scopes {
+                let index = match scopes.iter().position(|scope| scope.name == scope_name) {
+                    Some(index) => index,
+                    None => {
+                        scopes.push(Scope { name: scope_name, signals: Vec::new(), children: Vec::new() });
+                        scopes.len() - 1
+                    }
+                };
+                let scope = &mut scopes[index];
+                signals = Some(&mut scope.signals);
+                scopes = &mut scope.children;
+            }
+            // every path starts with the toplevel name so every signal is in at least one scope
+            if let Some(signals) = signals {
+                signals.push(i);
+            }
+        }
+        for scope in &toplevel {
+            self.write_scope(out, scope)?;
+        }
+        writeln!(out, "$enddefinitions $end")?;
+
+        if let Some((tick, values)) = &self.initial {
+            writeln!(out, "#{tick}")?;
+            writeln!(out, "$dumpvars")?;
+            for (i, value) in values.iter().enumerate() {
+                writeln!(out, "{}{}", value_char(*value), identifier(i))?;
+            }
+            writeln!(out, "$end")?;
+        }
+        for (tick, changes) in &self.changes {
+            writeln!(out, "#{tick}")?;
+            for (i, value) in changes {
+                writeln!(out, "{}{}", value_char(*value), identifier(*i))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(filename)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    fn write_scope(&self, out: &mut impl Write, scope: &Scope) -> std::io::Result<()> {
+        writeln!(out, "$scope module {} $end", escape(scope.name))?;
+        for signal in &scope.signals {
+            writeln!(out, "$var wire 1 {} {} $end", identifier(*signal), escape(&self.signals[*signal].name))?;
+        }
+        for child in &scope.children {
+            self.write_scope(out, child)?;
+        }
+        writeln!(out, "$upscope $end")
+    }
+}
+
+// every node in a gate, including all of the nodes in the gates inside of it if it is a subcircuit
+// the path of the toplevel on its own (just "main") means every node in the simulation
+pub fn nodes_in(simulation: &Simulation, gate_path: &str) -> Option<Vec<NodeKey>> {
+    let mut nodes = Vec::new();
+    if gate_path == path::TOPLEVEL_NAME {
+        for gate in simulation.toplevel_gates.iter() {
+            add_nodes_in(simulation, *gate, &mut nodes);
+        }
+    } else {
+        add_nodes_in(simulation, simulation.find_gate(gate_path)?, &mut nodes);
+    }
+    Some(nodes)
+}
+
+fn add_nodes_in(simulation: &Simulation, gate: GateKey, nodes: &mut Vec<NodeKey>) {
+    nodes.extend(Gate::inputs(&simulation.circuits, &simulation.gates, gate));
+    nodes.extend(Gate::outputs(&simulation.circuits, &simulation.gates, gate));
+    if let Gate::Custom(ck) = simulation.gates[gate] {
+        for subgate in simulation.circuits[ck].gates.iter() {
+            add_nodes_in(simulation, *subgate, nodes);
+        }
+    }
+}
+
+fn value_char(value: logic::Value) -> char {
+    match value {
+        logic::Value::H => '1',
+        logic::Value::L => '0',
+        logic::Value::Z => 'z',
+        logic::Value::X => 'x',
+    }
+}
+
+// identifiers are made of the printable ascii characters, so this is just the index in base 94
+fn identifier(mut index: usize) -> String {
+    const FIRST: u8 = b'!';
+    const NUM_CHARS: usize = (b'~' - b'!' + 1) as usize;
+
+    let mut identifier = String::new();
+    loop {
+        identifier.push((FIRST + (index % NUM_CHARS) as u8) as char);
+        index /= NUM_CHARS;
+        if index == 0 {
+            break identifier;
+        }
+        index -= 1;
+    }
+}
+
+// names are separated by whitespace in the file so they cannot have any in them
+fn escape(name: &str) -> String {
+    name.replace(char::is_whitespace, "_")
+}
@@ -9,6 +9,11 @@ pub(crate) mod lens;
 pub(crate) enum TargetedEvent {
     LeftMouseDown(graphics::Vector2f),
     RightMouseDown(graphics::Vector2f),
+    // positive delta is scrolling up
+    MouseWheelScrolled(graphics::Vector2f, f32),
+    // keys and text go to the view that has focus, or the view under the mouse if nothing does
+    KeyPressed(KeyEvent),
+    TextEntered(char),
 }
 #[derive(Copy, Clone)]
 pub(crate) enum GeneralEvent {
@@ -16,6 +21,26 @@ pub(crate) enum GeneralEvent {
     LeftMouseUp,
 }
 
+// also used by views to describe their keyboard shortcuts, by comparing against the KeyPressed events they get
+#[derive(Copy, Clone, PartialEq)]
+pub(crate) struct KeyEvent {
+    pub(crate) key: sfml::window::Key,
+    pub(crate) ctrl: bool,
+    pub(crate) shift: bool,
+    pub(crate) alt: bool,
+}
+impl KeyEvent {
+    pub(crate) const fn new(key: sfml::window::Key) -> KeyEvent {
+        KeyEvent { key, ctrl: false, shift: false, alt: false }
+    }
+    pub(crate) const fn ctrl(self) -> KeyEvent {
+        KeyEvent { ctrl: true, ..self }
+    }
+    pub(crate) const fn shift(self) -> KeyEvent {
+        KeyEvent { shift: true, ..self }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq)]
 pub(crate) struct SizeConstraints {
     pub(crate) min: graphics::Vector2f,
@@ -86,6 +111,9 @@ pub(crate) fn event(app: &crate::App, window: &graphics::RenderWindow, logic_gat
 
     match event {
         sfml::window::Event::MouseButtonPressed { button, x, y } => {
+            // clicking takes focus away from whatever had it, the view that was clicked on can take it back when it gets the event
+            app.focus.set(None);
+
             let mouse_position = graphics::Vector2f::new(x as f32, y as f32); // TODO: clean up casts (also clean up in rest of module too)
             let hovered = view_with_layout.find_hover(view_center, mouse_position);
             if let Some(hovered) = hovered {
@@ -97,9 +125,35 @@ pub(crate) fn event(app: &crate::App, window: &graphics::RenderWindow, logic_gat
             }
         }
 
-        sfml::window::Event::MouseMoved { x, y } => view_with_layout.general_event(app, logic_gates, GeneralEvent::MouseMoved(graphics::Vector2f::new(x as f32, y as f32))), // TODO: change the event to accept 2 i32s
+        sfml::window::Event::MouseWheelScrolled { wheel: sfml::window::mouse::Wheel::VerticalWheel, delta, x, y } => {
+            let mouse_position = graphics::Vector2f::new(x as f32, y as f32);
+            if let Some(hovered) = view_with_layout.find_hover(view_center, mouse_position) {
+                view_with_layout.send_targeted_event(app, logic_gates, hovered, TargetedEvent::MouseWheelScrolled(mouse_position, delta));
+            }
+        }
+
+        sfml::window::Event::MouseMoved { x, y } => {
+            // some views only find out where they are on the screen in find_hover, so it has to happen before general events too
+            let mouse_position = graphics::Vector2f::new(x as f32, y as f32); // TODO: change the event to accept 2 i32s
+            view_with_layout.find_hover(view_center, mouse_position);
+            view_with_layout.general_event(app, logic_gates, GeneralEvent::MouseMoved(mouse_position));
+        }
+
+        sfml::window::Event::MouseButtonReleased { button: sfml::window::mouse::Button::Left, x, y } => {
+            view_with_layout.find_hover(view_center, graphics::Vector2f::new(x as f32, y as f32));
+            view_with_layout.general_event(app, logic_gates, GeneralEvent::LeftMouseUp);
+        }
 
-        sfml::window::Event::MouseButtonReleased { button: sfml::window::mouse::Button::Left, x: _, y: _ } => view_with_layout.general_event(app, logic_gates, GeneralEvent::LeftMouseUp),
+        sfml::window::Event::KeyPressed { code, alt, ctrl, shift, scan: _, system: _ } => {
+            if let Some(target) = app.focus.get().or_else(|| view_with_layout.find_hover(view_center, window.mouse_position().as_other())) {
+                view_with_layout.send_targeted_event(app, logic_gates, target, TargetedEvent::KeyPressed(KeyEvent { key: code, ctrl, shift, alt }));
+            }
+        }
+        sfml::window::Event::TextEntered { unicode } => {
+            if let Some(target) = app.focus.get().or_else(|| view_with_layout.find_hover(view_center, window.mouse_position().as_other())) {
+                view_with_layout.send_targeted_event(app, logic_gates, target, TargetedEvent::TextEntered(unicode));
+            }
+        }
 
         _ => {}
     }
@@ -1,5 +1,17 @@
 #[derive(Copy, Clone, PartialEq, Eq)]
-pub(crate) struct ViewId(u64);
+pub(crate) struct ViewId(Kind);
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Kind {
+    Made(u64),
+    Keyed(u128),
+}
+impl ViewId {
+    // ids from ViewIdMaker are numbered in the order that views are made, so they change when views made before them come and go
+    // views that have to be found again after the views are made again (like the one with focus) use this instead, with a key that stays the same as long as the view exists
+    pub(crate) fn keyed(key: u128) -> ViewId {
+        ViewId(Kind::Keyed(key))
+    }
+}
 
 pub(crate) struct ViewIdMaker(u64);
 impl ViewIdMaker {
@@ -7,7 +19,7 @@ impl ViewIdMaker {
         ViewIdMaker(0)
     }
     pub(crate) fn next_id(&mut self) -> ViewId {
-        let id = ViewId(self.0);
+        let id = ViewId(Kind::Made(self.0));
         self.0 += 1;
         id
     }